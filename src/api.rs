@@ -0,0 +1,298 @@
+use crate::configuration::ApiConfig;
+use crate::crawlers::{Config, Health};
+use crate::models::Property;
+use chrono::{DateTime, Local, NaiveDateTime};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_derive::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Outcome of a watcher's last run.
+#[derive(Serialize, Clone, Debug)]
+pub struct WatcherStatus {
+  pub crawler: String,
+  pub city: String,
+  pub address: String,
+  pub finished: DateTime<Local>,
+  pub pages: u32,
+  pub results: usize,
+  pub parsed: usize,
+  /// number of errors by kind
+  pub errors: HashMap<&'static str, usize>,
+  /// error that stopped the watcher
+  pub error: Option<String>,
+  /// reason, if the portal served a block page
+  pub blocked: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct Status {
+  pub running: bool,
+  pub paused: bool,
+  pub last_run: Option<DateTime<Local>>,
+  pub next_run: Option<NaiveDateTime>,
+  pub watchers: Vec<WatcherStatus>,
+}
+
+/// State of the periodic runs, which is shared with the control api.
+pub struct Control {
+  status: Mutex<Status>,
+  /// recently found properties, the latest first
+  properties: Mutex<VecDeque<Property>>,
+  recent_properties: usize,
+  trigger: Notify,
+}
+
+impl Control {
+  pub fn new(config: &ApiConfig) -> Self {
+    Control {
+      status: Mutex::new(Status::default()),
+      properties: Mutex::new(VecDeque::new()),
+      recent_properties: config.recent_properties,
+      trigger: Notify::new(),
+    }
+  }
+
+  pub fn status(&self) -> Status {
+    self.status.lock().unwrap().clone()
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.status.lock().unwrap().paused
+  }
+
+  pub fn set_paused(&self, paused: bool) {
+    self.status.lock().unwrap().paused = paused;
+  }
+
+  pub fn started(&self) {
+    let mut status = self.status.lock().unwrap();
+    status.running = true;
+    status.last_run = Some(Local::now());
+  }
+
+  pub fn finished(&self, properties: &[Property], next_run: Option<NaiveDateTime>) {
+    let mut status = self.status.lock().unwrap();
+    status.running = false;
+    status.next_run = next_run;
+    let mut recent = self.properties.lock().unwrap();
+    for property in properties {
+      recent.push_front(property.clone());
+    }
+    recent.truncate(self.recent_properties);
+  }
+
+  /// Records the result of a watcher, replacing the one of its previous run.
+  pub fn record(&self, watcher: &Config, health: &Health) {
+    let result = WatcherStatus {
+      crawler: watcher.crawler.clone(),
+      city: watcher.city.clone(),
+      address: watcher.address.clone(),
+      finished: Local::now(),
+      pages: health.pages,
+      results: health.results,
+      parsed: health.parsed,
+      errors: health.errors.clone(),
+      error: health.error.as_ref().map(|error| error.to_string()),
+      blocked: health.blocked.clone(),
+    };
+    let mut status = self.status.lock().unwrap();
+    status
+      .watchers
+      .retain(|other| other.address != watcher.address || other.crawler != watcher.crawler);
+    status.watchers.push(result);
+  }
+
+  pub fn recent_properties(&self, limit: usize) -> Vec<Property> {
+    let recent = self.properties.lock().unwrap();
+    recent.iter().take(limit).cloned().collect()
+  }
+
+  /// Asks for all watchers to be run right away, even if paused.
+  pub fn trigger(&self) {
+    self.trigger.notify();
+  }
+
+  /// Completes once a run has been triggered, also if it was triggered during the last run.
+  pub async fn triggered(&self) {
+    self.trigger.notified().await;
+  }
+}
+
+/// Serves the control api in the background:
+/// - `GET /status`: state of the runs and the last result of each watcher
+/// - `POST /run`: runs all watchers right away
+/// - `GET /properties?limit=<n>`: recently found properties, the latest first
+/// - `POST /pause` and `POST /resume`: stops and restarts the scheduled runs
+pub fn serve(config: &ApiConfig, control: Arc<Control>) -> Result<(), String> {
+  let address: SocketAddr = config
+    .address
+    .parse()
+    .map_err(|_| format!("'{}' is not an address like 127.0.0.1:8080", config.address))?;
+  let builder = Server::try_bind(&address).map_err(|e| e.to_string())?;
+  let service = make_service_fn(move |_| {
+    let control = control.clone();
+    async move {
+      Ok::<_, Infallible>(service_fn(move |request| {
+        let control = control.clone();
+        async move { Ok::<_, Infallible>(handle(&control, request)) }
+      }))
+    }
+  });
+  let server = builder.serve(service);
+  println!("serving control api on http://{}.", address);
+  tokio::spawn(async move {
+    if let Err(e) = server.await {
+      eprintln!("Error while serving control api: {}", e);
+    }
+  });
+  Ok(())
+}
+
+fn handle(control: &Control, request: Request<Body>) -> Response<Body> {
+  match (request.method(), request.uri().path()) {
+    (&Method::GET, "/status") => json(StatusCode::OK, &control.status()),
+    (&Method::POST, "/run") => {
+      control.trigger();
+      json(StatusCode::ACCEPTED, &control.status())
+    }
+    (&Method::GET, "/properties") => {
+      let limit = request
+        .uri()
+        .query()
+        .and_then(|query| {
+          url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == "limit")
+            .and_then(|(_, value)| value.parse().ok())
+        })
+        .unwrap_or(control.recent_properties);
+      json(StatusCode::OK, &control.recent_properties(limit))
+    }
+    (&Method::POST, "/pause") => {
+      control.set_paused(true);
+      json(StatusCode::OK, &control.status())
+    }
+    (&Method::POST, "/resume") => {
+      control.set_paused(false);
+      json(StatusCode::OK, &control.status())
+    }
+    (_, "/status") | (_, "/run") | (_, "/properties") | (_, "/pause") | (_, "/resume") => {
+      empty(StatusCode::METHOD_NOT_ALLOWED)
+    }
+    _ => empty(StatusCode::NOT_FOUND),
+  }
+}
+
+fn json<T: serde::Serialize>(status: StatusCode, value: &T) -> Response<Body> {
+  match serde_json::to_string(value) {
+    Ok(body) => Response::builder()
+      .status(status)
+      .header("Content-Type", "application/json")
+      .body(Body::from(body))
+      .unwrap(),
+    Err(e) => {
+      eprintln!("Error while serializing api response: {}", e);
+      empty(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+  }
+}
+
+fn empty(status: StatusCode) -> Response<Body> {
+  Response::builder()
+    .status(status)
+    .body(Body::empty())
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{handle, Control};
+  use crate::configuration::ApiConfig;
+  use crate::models::Property;
+  use hyper::{Body, Method, Request, StatusCode};
+
+  fn control() -> Control {
+    Control::new(&ApiConfig {
+      enabled: true,
+      address: String::from("127.0.0.1:8080"),
+      recent_properties: 2,
+    })
+  }
+
+  fn request(method: Method, uri: &str) -> Request<Body> {
+    Request::builder()
+      .method(method)
+      .uri(uri)
+      .body(Body::empty())
+      .unwrap()
+  }
+
+  fn body(request: Request<Body>, control: &Control) -> (StatusCode, serde_json::Value) {
+    let response = handle(control, request);
+    let status = response.status();
+    let bytes = futures::executor::block_on(hyper::body::to_bytes(response.into_body())).unwrap();
+    (
+      status,
+      serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null),
+    )
+  }
+
+  #[test]
+  fn pause_and_resume() {
+    // GIVEN
+    let control = control();
+
+    // WHEN
+    let (paused_status, paused) = body(request(Method::POST, "/pause"), &control);
+    let is_paused = control.is_paused();
+    let (_, resumed) = body(request(Method::POST, "/resume"), &control);
+
+    // THEN
+    assert_eq!(paused_status, StatusCode::OK);
+    assert_eq!(paused["paused"], true);
+    assert!(is_paused);
+    assert_eq!(resumed["paused"], false);
+    assert!(!control.is_paused());
+  }
+
+  #[test]
+  fn recent_properties() {
+    // GIVEN
+    let control = control();
+    control.finished(&[Property::dummy(500.0, 50.0, 2.0)], None);
+    control.finished(
+      &[
+        Property::dummy(600.0, 60.0, 2.0),
+        Property::dummy(700.0, 70.0, 3.0),
+      ],
+      None,
+    );
+
+    // WHEN
+    let (status, all) = body(request(Method::GET, "/properties"), &control);
+    let (_, limited) = body(request(Method::GET, "/properties?limit=1"), &control);
+
+    // THEN
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(all.as_array().unwrap().len(), 2);
+    assert_eq!(all[0]["data"]["cold_rent"], 700.0);
+    assert_eq!(limited.as_array().unwrap().len(), 1);
+  }
+
+  #[test]
+  fn unknown_requests() {
+    let control = control();
+    assert_eq!(
+      body(request(Method::GET, "/run"), &control).0,
+      StatusCode::METHOD_NOT_ALLOWED
+    );
+    assert_eq!(
+      body(request(Method::GET, "/unknown"), &control).0,
+      StatusCode::NOT_FOUND
+    );
+  }
+}
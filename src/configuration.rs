@@ -1,4 +1,11 @@
+use crate::crawlers::charset;
 use crate::crawlers::Config as CrawlerConfig;
+use crate::crawlers::FixtureMode;
+use crate::crawlers::GenericConfig as GenericCrawlerConfig;
+use crate::crawlers::JsonStateConfig;
+use crate::models::{Currency, Gender};
+use crate::schedule::{Cron, QuietHours};
+use chrono::NaiveDate;
 use config::{Config, File};
 use serde_derive::{Deserialize, Serialize};
 
@@ -6,10 +13,37 @@ use serde_derive::{Deserialize, Serialize};
 pub struct CriteriaConfig {
   pub price_min: Option<f32>,
   pub price_max: Option<f32>,
+  /// maximum rent including service charges
+  pub warm_rent_max: Option<f32>,
   pub squaremeters_min: Option<f32>,
   pub squaremeters_max: Option<f32>,
   pub rooms_min: Option<f32>,
   pub rooms_max: Option<f32>,
+  /// currency of the price limits above
+  #[serde(default)]
+  pub currency: Currency,
+  /// price limits for properties priced in other currencies, which are not checked against any limits otherwise
+  #[serde(default)]
+  pub prices: Vec<PriceLimits>,
+  /// gender of the person looking for a room, rooms offered to others only are dropped
+  pub gender: Option<Gender>,
+  /// age of the person looking for a room
+  pub age: Option<u32>,
+  /// maximum number of people already living in the shared flat of a room
+  pub flatmates_max: Option<u32>,
+  /// properties have to be available on or before this day
+  pub available_before: Option<NaiveDate>,
+  /// properties that are only available for a limited time have to be available for at least as many months
+  pub min_months: Option<u32>,
+}
+
+/// Price limits of the criteria for properties priced in the given currency.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PriceLimits {
+  pub currency: Currency,
+  pub price_min: Option<f32>,
+  pub price_max: Option<f32>,
+  pub warm_rent_max: Option<f32>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -42,9 +76,88 @@ pub struct DynamoDbConfig {
   pub region: String,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FixturesConfig {
+  pub mode: FixtureMode,
+  pub directory: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HttpConfig {
+  pub user_agent: String,
+  /// seconds until a request is aborted
+  pub timeout: u64,
+  pub connect_timeout: u64,
+  /// number of retries of requests that failed with 5xx, 429, a timeout or a failed connection
+  pub retries: u32,
+  /// milliseconds to wait before the first retry, doubled on every further retry
+  pub backoff: u64,
+  /// maximum seconds to wait before a retry, a request whose Retry-After asks for longer is not retried
+  pub max_retry_delay: u64,
+  /// minimum milliseconds between two requests to the same domain
+  pub domain_delay: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SchedulerConfig {
+  /// number of watchers that are crawled at the same time
+  pub concurrency: usize,
+  /// number of watchers of the same domain that are crawled at the same time
+  pub domain_concurrency: usize,
+  /// seconds after which a watcher is aborted, including all of its pages and retries
+  pub watcher_timeout: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PipelineConfig {
+  /// number of watchers whose properties are filtered at the same time
+  pub filter_concurrency: usize,
+  /// number of properties whose expose is fetched and which are enriched at the same time
+  pub enricher_concurrency: usize,
+  /// number of properties that are passed to the observers at the same time
+  pub observer_concurrency: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShutdownConfig {
+  /// seconds a run may take to finish, once the process has been asked to stop
+  pub deadline: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ApiConfig {
+  /// whether the control api is served while running periodically
+  pub enabled: bool,
+  /// address the api is bound to, only reachable from the same host by default
+  pub address: String,
+  /// number of recently found properties that are kept
+  pub recent_properties: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BlockingConfig {
+  /// responses with less bytes are considered to be block pages
+  pub min_body_size: usize,
+  /// texts that only occur on block or captcha pages
+  pub markers: Vec<String>,
+  /// seconds a portal is paused after it blocked us, doubled on every further block
+  pub cooldown: u64,
+  pub max_cooldown: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HealthConfig {
+  pub min_success_ratio: f32,
+  pub consecutive_runs: u32,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ApplicationConfig {
   pub watchers: Vec<CrawlerConfig>,
+  #[serde(default = "default_crawlers")]
+  pub crawlers: Vec<GenericCrawlerConfig>,
+  #[serde(default = "default_json_crawlers")]
+  pub json_crawlers: Vec<JsonStateConfig>,
   #[serde(default = "default_test")]
   pub test: bool,
   #[serde(default = "default_run_periodically")]
@@ -53,8 +166,6 @@ pub struct ApplicationConfig {
   pub interval: u64,
   #[serde(default = "default_initial_run")]
   pub initial_run: bool,
-  #[serde(default = "default_thread_count")]
-  pub thread_count: i32,
   #[serde(default = "default_dynamodb")]
   pub dynamodb: DynamoDbConfig,
   #[serde(default = "default_nominatim")]
@@ -67,6 +178,22 @@ pub struct ApplicationConfig {
   pub csv: CSVConfig,
   #[serde(default = "default_criteria")]
   pub criteria: CriteriaConfig,
+  #[serde(default = "default_fixtures")]
+  pub fixtures: FixturesConfig,
+  #[serde(default = "default_http")]
+  pub http: HttpConfig,
+  #[serde(default = "default_scheduler")]
+  pub scheduler: SchedulerConfig,
+  #[serde(default = "default_pipeline")]
+  pub pipeline: PipelineConfig,
+  #[serde(default = "default_shutdown")]
+  pub shutdown: ShutdownConfig,
+  #[serde(default = "default_api")]
+  pub api: ApiConfig,
+  #[serde(default = "default_blocking")]
+  pub blocking: BlockingConfig,
+  #[serde(default = "default_health")]
+  pub health: HealthConfig,
   #[serde(default = "default_observers")]
   pub observers: Vec<String>,
   #[serde(default = "default_enrichers")]
@@ -87,9 +214,6 @@ fn default_interval() -> u64 {
 fn default_initial_run() -> bool {
   false
 }
-fn default_thread_count() -> i32 {
-  1
-}
 fn default_nominatim() -> NominatimConfig {
   NominatimConfig {
     nominatim_url: String::from(""),
@@ -124,12 +248,93 @@ fn default_criteria() -> CriteriaConfig {
   CriteriaConfig {
     price_min: None,
     price_max: None,
+    warm_rent_max: None,
     squaremeters_min: None,
     squaremeters_max: None,
     rooms_min: None,
     rooms_max: None,
+    currency: Currency::Eur,
+    prices: vec![],
+    gender: None,
+    age: None,
+    flatmates_max: None,
+    available_before: None,
+    min_months: None,
+  }
+}
+fn default_fixtures() -> FixturesConfig {
+  FixturesConfig {
+    mode: FixtureMode::Off,
+    directory: String::from("fixtures"),
+  }
+}
+fn default_http() -> HttpConfig {
+  HttpConfig {
+    user_agent: default_user_agent(),
+    timeout: 30,
+    connect_timeout: 10,
+    retries: 3,
+    backoff: 1000,
+    max_retry_delay: 60,
+    domain_delay: 1000,
   }
 }
+fn default_user_agent() -> String {
+  format!("properwatcher/{}", env!("CARGO_PKG_VERSION"))
+}
+fn default_scheduler() -> SchedulerConfig {
+  SchedulerConfig {
+    concurrency: 4,
+    domain_concurrency: 2,
+    watcher_timeout: 300,
+  }
+}
+fn default_pipeline() -> PipelineConfig {
+  PipelineConfig {
+    filter_concurrency: 2,
+    enricher_concurrency: 4,
+    observer_concurrency: 4,
+  }
+}
+fn default_shutdown() -> ShutdownConfig {
+  ShutdownConfig { deadline: 8 }
+}
+fn default_api() -> ApiConfig {
+  ApiConfig {
+    enabled: false,
+    address: String::from("127.0.0.1:8080"),
+    recent_properties: 100,
+  }
+}
+fn default_blocking() -> BlockingConfig {
+  BlockingConfig {
+    min_body_size: 1024,
+    markers: default_block_markers(),
+    cooldown: 900,
+    max_cooldown: 86400,
+  }
+}
+fn default_block_markers() -> Vec<String> {
+  // only markers of interstitial challenge pages, captcha widgets are embedded in ordinary pages as well
+  vec![
+    String::from("distil_r_captcha"),
+    String::from("_Incapsula_Resource"),
+    String::from("cf-browser-verification"),
+    String::from("px-captcha"),
+  ]
+}
+fn default_health() -> HealthConfig {
+  HealthConfig {
+    min_success_ratio: 0.5,
+    consecutive_runs: 3,
+  }
+}
+fn default_crawlers() -> Vec<GenericCrawlerConfig> {
+  vec![]
+}
+fn default_json_crawlers() -> Vec<JsonStateConfig> {
+  vec![]
+}
 fn default_observers() -> Vec<String> {
   vec![]
 }
@@ -144,7 +349,6 @@ pub fn read(config_path: String) -> ApplicationConfig {
   let mut config = Config::new();
   config.merge(File::with_name(config_path.as_str())).unwrap();
   let test = config.get("test").unwrap_or(false);
-  let thread_count = config.get("thread_count").unwrap_or(2);
   let interval = config.get("interval").unwrap_or(300);
   let initial_run = config.get("initial_run").unwrap_or(false);
   let run_periodically = config.get("run_periodically").unwrap_or(true);
@@ -176,15 +380,82 @@ pub fn read(config_path: String) -> ApplicationConfig {
 
   let criteria_price_min = config.get("criteria.price_min").ok();
   let criteria_price_max = config.get("criteria.price_max").ok();
+  let criteria_warm_rent_max = config.get("criteria.warm_rent_max").ok();
   let criteria_squaremeters_min = config.get("criteria.squaremeters_min").ok();
   let criteria_squaremeters_max = config.get("criteria.squaremeters_max").ok();
   let criteria_rooms_min = config.get("criteria.rooms_min").ok();
   let criteria_rooms_max = config.get("criteria.rooms_max").ok();
+  let criteria_currency: Option<String> = config.get("criteria.currency").ok();
+  let criteria_prices: Vec<PriceLimits> = match config.get_array("criteria.prices") {
+    Ok(_) => config
+      .get("criteria.prices")
+      .expect("criteria.prices could not be read, each needs a currency of EUR or CHF"),
+    Err(_) => vec![],
+  };
+  let criteria_gender: Option<String> = config.get("criteria.gender").ok();
+  let criteria_age = config.get("criteria.age").ok();
+  let criteria_flatmates_max = config.get("criteria.flatmates_max").ok();
+  let criteria_available_before: Option<String> = config.get("criteria.available_before").ok();
+  let criteria_min_months = config.get("criteria.min_months").ok();
+
+  let fixtures_mode: String = config.get("fixtures.mode").unwrap_or(String::from("off"));
+  let fixtures_directory = config
+    .get("fixtures.directory")
+    .unwrap_or(String::from("fixtures"));
+
+  let http_user_agent = config
+    .get("http.user_agent")
+    .unwrap_or(default_user_agent());
+  let http_timeout = config.get("http.timeout").unwrap_or(30);
+  let http_connect_timeout = config.get("http.connect_timeout").unwrap_or(10);
+  let http_retries = config.get("http.retries").unwrap_or(3);
+  let http_backoff = config.get("http.backoff").unwrap_or(1000);
+  let http_max_retry_delay = config.get("http.max_retry_delay").unwrap_or(60);
+  let http_domain_delay = config.get("http.domain_delay").unwrap_or(1000);
+
+  let scheduler_concurrency = config.get("scheduler.concurrency").unwrap_or(4);
+  let scheduler_domain_concurrency = config.get("scheduler.domain_concurrency").unwrap_or(2);
+  let scheduler_watcher_timeout = config.get("scheduler.watcher_timeout").unwrap_or(300);
+
+  let pipeline_filter_concurrency = config.get("pipeline.filter_concurrency").unwrap_or(2);
+  let pipeline_enricher_concurrency = config.get("pipeline.enricher_concurrency").unwrap_or(4);
+  let pipeline_observer_concurrency = config.get("pipeline.observer_concurrency").unwrap_or(4);
+
+  let shutdown_deadline = config.get("shutdown.deadline").unwrap_or(8);
+
+  let api_enabled = config.get("api.enabled").unwrap_or(false);
+  let api_address = config
+    .get("api.address")
+    .unwrap_or(String::from("127.0.0.1:8080"));
+  let api_recent_properties = config.get("api.recent_properties").unwrap_or(100);
+
+  let blocking_min_body_size = config.get("blocking.min_body_size").unwrap_or(1024);
+  let blocking_markers = config
+    .get("blocking.markers")
+    .unwrap_or(default_block_markers());
+  let blocking_cooldown = config.get("blocking.cooldown").unwrap_or(900);
+  let blocking_max_cooldown = config.get("blocking.max_cooldown").unwrap_or(86400);
+
+  let health_min_success_ratio = config.get("health.min_success_ratio").unwrap_or(0.5);
+  let health_consecutive_runs = config.get("health.consecutive_runs").unwrap_or(3);
 
   let filters = config.get("filters").unwrap_or(vec![]);
   let enrichers = config.get("enrichers").unwrap_or(vec![]);
   let observers = config.get("observers").unwrap_or(vec![]);
 
+  let generic_crawler_configs: Vec<GenericCrawlerConfig> = match config.get_array("crawler") {
+    Ok(_) => config
+      .get("crawler")
+      .expect("Crawler definitions could not be read"),
+    Err(_) => vec![],
+  };
+  let json_crawler_configs: Vec<JsonStateConfig> = match config.get_array("json_crawler") {
+    Ok(_) => config
+      .get("json_crawler")
+      .expect("JSON crawler definitions could not be read"),
+    Err(_) => vec![],
+  };
+
   let mut crawler_configs: Vec<CrawlerConfig> = vec![];
   let watcher_arr = config.get_array("watcher").unwrap();
   for watcher in watcher_arr {
@@ -207,6 +478,48 @@ pub fn read(config_path: String) -> ApplicationConfig {
       .to_owned()
       .into_str()
       .unwrap();
+    let max_pages = crawler_values
+      .get("max_pages")
+      .map(|value| value.to_owned().into_int().unwrap() as u32)
+      .unwrap_or(1);
+    let incremental = crawler_values
+      .get("incremental")
+      .map(|value| value.to_owned().into_bool().unwrap())
+      .unwrap_or(true);
+    let encoding = crawler_values
+      .get("encoding")
+      .map(|value| value.to_owned().into_str().unwrap());
+    if let Some(ref label) = encoding {
+      if charset::for_label(label).is_none() {
+        panic!("Unknown encoding '{}' of watcher {}", label, crawler);
+      }
+    }
+    let interval = crawler_values
+      .get("interval")
+      .map(|value| value.to_owned().into_int().unwrap() as u64);
+    let cron = crawler_values
+      .get("cron")
+      .map(|value| value.to_owned().into_str().unwrap());
+    if let Some(ref expression) = cron {
+      if let Err(e) = expression.parse::<Cron>() {
+        panic!(
+          "Invalid cron '{}' of watcher {}: {}",
+          expression, crawler, e
+        );
+      }
+    }
+    let jitter = crawler_values
+      .get("jitter")
+      .map(|value| value.to_owned().into_int().unwrap() as u64)
+      .unwrap_or(0);
+    let quiet_hours = crawler_values
+      .get("quiet_hours")
+      .map(|value| value.to_owned().into_str().unwrap());
+    if let Some(ref span) = quiet_hours {
+      if let Err(e) = span.parse::<QuietHours>() {
+        panic!("Invalid quiet_hours of watcher {}: {}", crawler, e);
+      }
+    }
     let crawler_config = CrawlerConfig {
       city: crawler_values
         .get("city")
@@ -223,6 +536,13 @@ pub fn read(config_path: String) -> ApplicationConfig {
       crawler,
       contract_type: contract.parse().unwrap(),
       property_type: property.parse().unwrap(),
+      max_pages,
+      incremental,
+      encoding,
+      interval,
+      cron,
+      jitter,
+      quiet_hours,
     };
     crawler_configs.push(crawler_config);
   }
@@ -231,7 +551,6 @@ pub fn read(config_path: String) -> ApplicationConfig {
     test,
     interval,
     initial_run,
-    thread_count: thread_count,
     run_periodically,
     nominatim: NominatimConfig {
       nominatim_url: nominatim_nominatim_url,
@@ -256,14 +575,84 @@ pub fn read(config_path: String) -> ApplicationConfig {
     criteria: CriteriaConfig {
       price_min: criteria_price_min,
       price_max: criteria_price_max,
+      warm_rent_max: criteria_warm_rent_max,
       squaremeters_min: criteria_squaremeters_min,
       squaremeters_max: criteria_squaremeters_max,
       rooms_min: criteria_rooms_min,
       rooms_max: criteria_rooms_max,
+      currency: criteria_currency
+        .map(|currency| {
+          currency.parse().unwrap_or_else(|_| {
+            panic!(
+              "criteria.currency has to be EUR or CHF, but is '{}'",
+              currency
+            )
+          })
+        })
+        .unwrap_or_default(),
+      prices: criteria_prices,
+      gender: criteria_gender.map(|gender| {
+        gender.parse().unwrap_or_else(|_| {
+          panic!(
+            "criteria.gender has to be female, male or any, but is '{}'",
+            gender
+          )
+        })
+      }),
+      age: criteria_age,
+      flatmates_max: criteria_flatmates_max,
+      available_before: criteria_available_before.map(|date| {
+        NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+          .expect("criteria.available_before has to be given as yyyy-mm-dd")
+      }),
+      min_months: criteria_min_months,
+    },
+    fixtures: FixturesConfig {
+      mode: fixtures_mode.parse().unwrap(),
+      directory: fixtures_directory,
+    },
+    http: HttpConfig {
+      user_agent: http_user_agent,
+      timeout: http_timeout,
+      connect_timeout: http_connect_timeout,
+      retries: http_retries,
+      backoff: http_backoff,
+      max_retry_delay: http_max_retry_delay,
+      domain_delay: http_domain_delay,
+    },
+    scheduler: SchedulerConfig {
+      concurrency: scheduler_concurrency,
+      domain_concurrency: scheduler_domain_concurrency,
+      watcher_timeout: scheduler_watcher_timeout,
+    },
+    pipeline: PipelineConfig {
+      filter_concurrency: pipeline_filter_concurrency,
+      enricher_concurrency: pipeline_enricher_concurrency,
+      observer_concurrency: pipeline_observer_concurrency,
+    },
+    shutdown: ShutdownConfig {
+      deadline: shutdown_deadline,
+    },
+    api: ApiConfig {
+      enabled: api_enabled,
+      address: api_address,
+      recent_properties: api_recent_properties,
+    },
+    blocking: BlockingConfig {
+      min_body_size: blocking_min_body_size,
+      markers: blocking_markers,
+      cooldown: blocking_cooldown,
+      max_cooldown: blocking_max_cooldown,
+    },
+    health: HealthConfig {
+      min_success_ratio: health_min_success_ratio,
+      consecutive_runs: health_consecutive_runs,
     },
     observers,
     filters,
     enrichers,
     watchers: crawler_configs,
+    crawlers: generic_crawler_configs,
+    json_crawlers: json_crawler_configs,
   }
 }
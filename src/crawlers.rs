@@ -1,31 +1,89 @@
+mod breaker;
+pub mod charset;
 mod config;
 mod crawler;
 mod executor;
+pub mod fixtures;
+mod generic;
+mod health;
+mod homegate;
+mod http;
+mod immobilo;
+mod immonet;
 mod immoscout;
 mod immowelt;
+mod jsonstate;
+mod kleinanzeigen;
+pub mod quantity;
+mod scheduler;
+mod schema;
 mod sueddeutsche;
 mod wggesucht;
+mod willhaben;
 mod wohnungsboerse;
 
+pub use crate::crawlers::breaker::CircuitBreaker;
 pub use crate::crawlers::config::Config;
+pub use crate::crawlers::config::GenericConfig;
+pub use crate::crawlers::config::JsonStateConfig;
+pub use crate::crawlers::crawler::Context;
 pub use crate::crawlers::crawler::Crawler;
 pub use crate::crawlers::crawler::Error;
 pub use crate::crawlers::crawler::Metadata;
 pub use crate::crawlers::executor::execute;
+pub use crate::crawlers::executor::fetch_expose;
+pub use crate::crawlers::fixtures::FixtureMode;
+pub use crate::crawlers::generic::GenericCrawler;
+pub use crate::crawlers::health::{Health, HealthMonitor};
+pub use crate::crawlers::homegate::Homegate;
+pub use crate::crawlers::http::HttpClient;
+pub use crate::crawlers::immobilo::Immobilo;
+pub use crate::crawlers::immonet::Immonet;
 pub use crate::crawlers::immoscout::ImmoScout;
 pub use crate::crawlers::immowelt::ImmoWelt;
+pub use crate::crawlers::jsonstate::JsonStateCrawler;
+pub use crate::crawlers::kleinanzeigen::Kleinanzeigen;
+pub use crate::crawlers::scheduler::crawl;
 pub use crate::crawlers::sueddeutsche::Sueddeutsche;
 pub use crate::crawlers::wggesucht::WGGesucht;
+pub use crate::crawlers::willhaben::Willhaben;
 pub use crate::crawlers::wohnungsboerse::Wohnungsboerse;
 
-pub fn get_crawlers() -> Vec<Box<dyn Crawler>> {
-  vec![
-    Box::new(ImmoWelt::new()),
-    Box::new(WGGesucht {}),
-    Box::new(Sueddeutsche::new()),
-    Box::new(ImmoScout {}),
-    Box::new(Wohnungsboerse {}),
-  ]
+use crate::ApplicationConfig;
+
+pub fn get_crawlers(app_config: &ApplicationConfig) -> Vec<Box<dyn Crawler>> {
+  // configured crawlers come first, so they can replace built-in ones of the same name
+  let mut crawlers: Vec<Box<dyn Crawler>> = app_config
+    .crawlers
+    .iter()
+    .filter_map(|config| match GenericCrawler::new(config) {
+      Ok(crawler) => Some(Box::new(crawler) as Box<dyn Crawler>),
+      Err(e) => {
+        eprintln!("Error while initializing crawler {}: {}", config.name, e);
+        None
+      }
+    })
+    .collect();
+  crawlers.extend(app_config.json_crawlers.iter().filter_map(
+    |config| match JsonStateCrawler::new(config) {
+      Ok(crawler) => Some(Box::new(crawler) as Box<dyn Crawler>),
+      Err(e) => {
+        eprintln!("Error while initializing crawler {}: {}", config.name, e);
+        None
+      }
+    },
+  ));
+  crawlers.push(Box::new(ImmoWelt::new()));
+  crawlers.push(Box::new(WGGesucht {}));
+  crawlers.push(Box::new(Sueddeutsche::new()));
+  crawlers.push(Box::new(ImmoScout {}));
+  crawlers.push(Box::new(Wohnungsboerse {}));
+  crawlers.push(Box::new(Kleinanzeigen {}));
+  crawlers.push(Box::new(Immonet {}));
+  crawlers.push(Box::new(Immobilo {}));
+  crawlers.push(Box::new(Willhaben {}));
+  crawlers.push(Box::new(Homegate {}));
+  crawlers
 }
 
 pub fn get_crawler<'a>(
@@ -37,7 +95,5 @@ pub fn get_crawler<'a>(
       return Ok(crawler);
     }
   }
-  Err(Error {
-    message: String::from(format!("Could not find crawler with name: {}", name)),
-  })
+  Err(Error::UnknownCrawler(name.to_owned()))
 }
@@ -0,0 +1,149 @@
+use crate::configuration::BlockingConfig;
+use chrono::{DateTime, Duration, Local};
+use std::collections::HashMap;
+
+struct Block {
+  /// number of consecutive runs the portal has blocked us
+  count: u32,
+  until: DateTime<Local>,
+  reason: String,
+}
+
+/// Pauses portals that blocked our requests, with a cooldown that escalates on every further block.
+pub struct CircuitBreaker {
+  blocks: HashMap<String, Block>,
+}
+
+impl CircuitBreaker {
+  pub fn new() -> Self {
+    CircuitBreaker {
+      blocks: HashMap::new(),
+    }
+  }
+
+  /// Returns the end of the cooldown, if the portal must not be requested right now.
+  pub fn cooldown(&self, portal: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    self
+      .blocks
+      .get(portal)
+      .filter(|block| block.until > now)
+      .map(|block| block.until)
+  }
+
+  /// Opens the breaker for the portal and returns a message describing the block.
+  pub fn record_block(
+    &mut self,
+    config: &BlockingConfig,
+    portal: &str,
+    reason: String,
+    now: DateTime<Local>,
+  ) -> String {
+    let count = self.blocks.get(portal).map_or(0, |block| block.count) + 1;
+    let seconds = config
+      .cooldown
+      .saturating_mul(2u64.saturating_pow(count - 1))
+      .min(config.max_cooldown);
+    let until = now + Duration::seconds(seconds as i64);
+    self.blocks.insert(
+      portal.to_owned(),
+      Block {
+        count,
+        until,
+        reason: reason.clone(),
+      },
+    );
+    format!(
+      "{} blocked our requests ({}), will pause it until {} (block #{}).",
+      portal,
+      reason,
+      until.format("%Y-%m-%d %H:%M"),
+      count
+    )
+  }
+
+  /// Closes the breaker after a successful run and returns a message, if the portal has been blocked before.
+  pub fn record_success(&mut self, portal: &str) -> Option<String> {
+    self.blocks.remove(portal).map(|block| {
+      format!(
+        "{} can be crawled again after {} block(s).",
+        portal, block.count
+      )
+    })
+  }
+
+  /// Describes all portals that are currently paused.
+  pub fn describe(&self, now: DateTime<Local>) -> Vec<String> {
+    let mut descriptions: Vec<String> = self
+      .blocks
+      .iter()
+      .filter(|(_, block)| block.until > now)
+      .map(|(portal, block)| {
+        format!(
+          "{} is paused until {} (block #{}: {})",
+          portal,
+          block.until.format("%Y-%m-%d %H:%M"),
+          block.count,
+          block.reason
+        )
+      })
+      .collect();
+    descriptions.sort();
+    descriptions
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::CircuitBreaker;
+  use crate::configuration::BlockingConfig;
+  use chrono::{Duration, Local};
+
+  fn config() -> BlockingConfig {
+    BlockingConfig {
+      min_body_size: 1024,
+      markers: vec![],
+      cooldown: 600,
+      max_cooldown: 3600,
+    }
+  }
+
+  #[test]
+  fn escalate_cooldown() {
+    // GIVEN
+    let mut breaker = CircuitBreaker::new();
+    let now = Local::now();
+
+    // WHEN
+    breaker.record_block(&config(), "immoscout", String::from("captcha"), now);
+    let first = breaker.cooldown("immoscout", now);
+    breaker.record_block(&config(), "immoscout", String::from("captcha"), now);
+    let second = breaker.cooldown("immoscout", now);
+    for _ in 0..5 {
+      breaker.record_block(&config(), "immoscout", String::from("captcha"), now);
+    }
+    let capped = breaker.cooldown("immoscout", now);
+
+    // THEN
+    assert_eq!(first, Some(now + Duration::seconds(600)));
+    assert_eq!(second, Some(now + Duration::seconds(1200)));
+    assert_eq!(capped, Some(now + Duration::seconds(3600)));
+    assert_eq!(breaker.cooldown("immowelt", now), None);
+  }
+
+  #[test]
+  fn close_after_cooldown_and_success() {
+    // GIVEN
+    let mut breaker = CircuitBreaker::new();
+    let now = Local::now();
+    breaker.record_block(&config(), "immoscout", String::from("status 403"), now);
+
+    // WHEN
+    let later = breaker.cooldown("immoscout", now + Duration::seconds(601));
+    let recovered = breaker.record_success("immoscout");
+
+    // THEN
+    assert_eq!(later, None);
+    assert!(recovered.is_some());
+    assert!(breaker.record_success("immoscout").is_none());
+  }
+}
@@ -0,0 +1,120 @@
+use encoding_rs::Encoding;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+/// Number of bytes at the start of a document, that are searched for a `<meta>` charset declaration.
+const PRESCAN_LENGTH: usize = 4096;
+
+/// Decodes a response body into text.
+/// The encoding is chosen in the following order: the encoding forced by the watcher,
+/// a byte order mark, the charset of the `Content-Type` header, a `<meta>` declaration
+/// within the document and finally the default encoding of the crawler.
+pub fn decode(
+  body: &[u8],
+  content_type: Option<&str>,
+  forced: Option<&'static Encoding>,
+  default: &'static Encoding,
+) -> String {
+  let encoding = forced
+    .or_else(|| Encoding::for_bom(body).map(|(encoding, _)| encoding))
+    .or_else(|| content_type.and_then(from_content_type))
+    .or_else(|| from_meta(body))
+    .unwrap_or(default);
+  let (decoded, _, _) = encoding.decode(body);
+  decoded.into_owned()
+}
+
+/// Looks up an encoding by any of its labels, e.g. `utf8`, `latin1` or `windows-1252`.
+pub fn for_label(label: &str) -> Option<&'static Encoding> {
+  Encoding::for_label(label.trim().as_bytes())
+}
+
+fn from_content_type(content_type: &str) -> Option<&'static Encoding> {
+  content_type
+    .split(';')
+    .filter_map(|parameter| {
+      let mut pair = parameter.splitn(2, '=');
+      match (pair.next(), pair.next()) {
+        (Some(name), Some(value)) if name.trim().eq_ignore_ascii_case("charset") => Some(value),
+        _ => None,
+      }
+    })
+    .next()
+    .and_then(|value| for_label(value.trim_matches(|c| c == '"' || c == '\'' || c == ' ')))
+}
+
+fn from_meta(body: &[u8]) -> Option<&'static Encoding> {
+  lazy_static! {
+    static ref META_REGEX: Regex =
+      Regex::new(r#"(?i-u)<meta[^>]+charset\s*=\s*["']?\s*([a-z0-9_:.-]+)"#).unwrap();
+  }
+  let prescan = &body[..body.len().min(PRESCAN_LENGTH)];
+  META_REGEX
+    .captures(prescan)
+    .and_then(|captures| captures.get(1))
+    .and_then(|label| Encoding::for_label(label.as_bytes()))
+    .map(|encoding| {
+      // a document that could be read up to its declaration can not be encoded in utf-16
+      if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE {
+        encoding_rs::UTF_8
+      } else {
+        encoding
+      }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{decode, for_label};
+
+  const LATIN1_BODY: &[u8] = b"<html><head><meta http-equiv=\"Content-Type\" content=\"text/html; charset=iso-8859-1\"></head><body>M\xfcnchen</body></html>";
+
+  #[test]
+  fn decode_meta_charset() {
+    // WHEN
+    let decoded = decode(LATIN1_BODY, None, None, encoding_rs::UTF_8);
+
+    // THEN
+    assert!(decoded.contains("München"));
+  }
+
+  #[test]
+  fn prefer_content_type() {
+    // GIVEN
+    let body = "<html><head><meta charset=\"iso-8859-1\"></head><body>München</body></html>";
+
+    // WHEN
+    let decoded = decode(
+      body.as_bytes(),
+      Some("text/html; charset=\"UTF-8\""),
+      None,
+      encoding_rs::WINDOWS_1252,
+    );
+
+    // THEN
+    assert!(decoded.contains("München"));
+  }
+
+  #[test]
+  fn fall_back_to_default() {
+    // WHEN
+    let decoded = decode(b"<p>Gr\xf6\xdfe</p>", None, None, encoding_rs::WINDOWS_1252);
+
+    // THEN
+    assert_eq!(decoded, "<p>Größe</p>");
+  }
+
+  #[test]
+  fn force_encoding() {
+    // WHEN
+    let decoded = decode(
+      LATIN1_BODY,
+      Some("text/html; charset=utf-8"),
+      for_label("latin1"),
+      encoding_rs::UTF_8,
+    );
+
+    // THEN
+    assert!(decoded.contains("München"));
+  }
+}
@@ -1,4 +1,5 @@
 use crate::models::ContractType;
+use crate::models::Currency;
 use crate::models::PropertyType;
 use serde_derive::{Deserialize, Serialize};
 
@@ -9,4 +10,137 @@ pub struct Config {
   pub crawler: String,
   pub property_type: PropertyType,
   pub contract_type: ContractType,
+  #[serde(default = "default_max_pages")]
+  pub max_pages: u32,
+  #[serde(default = "default_incremental")]
+  pub incremental: bool,
+  /// Label of an encoding that overrides the charset declared by the portal.
+  pub encoding: Option<String>,
+  /// Seconds between two runs of the watcher, the global interval is used if missing.
+  pub interval: Option<u64>,
+  /// Cron expression (minute hour day month weekday) of the watcher's runs, replaces the interval.
+  pub cron: Option<String>,
+  /// Maximum number of seconds each run is randomly delayed by.
+  #[serde(default)]
+  pub jitter: u64,
+  /// Daily time span in which the watcher is not run, eg. "23:00-07:00".
+  pub quiet_hours: Option<String>,
+}
+
+fn default_max_pages() -> u32 {
+  1
+}
+
+fn default_incremental() -> bool {
+  true
+}
+
+/// Definition of a crawler that is set up from the configuration (see `GenericCrawler`).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GenericConfig {
+  pub name: String,
+  pub selector: String,
+  #[serde(default = "default_encoding")]
+  pub encoding: String,
+  /// How numbers are written on the portal: de, at, ch or en.
+  #[serde(default = "default_locale")]
+  pub locale: String,
+  /// Currency of the prices on the portal: EUR or CHF.
+  #[serde(default)]
+  pub currency: Currency,
+  /// Url of a property, where `{externalid}` will be replaced by the property's id.
+  pub url: String,
+  pub fields: FieldsConfig,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FieldsConfig {
+  pub externalid: FieldConfig,
+  pub title: FieldConfig,
+  pub address: FieldConfig,
+  /// Cold rent or purchase price, depending on the watcher's contract type.
+  pub price: FieldConfig,
+  pub squaremeters: FieldConfig,
+  pub rooms: FieldConfig,
+  pub plot_squaremeters: Option<FieldConfig>,
+  pub warm_rent: Option<FieldConfig>,
+  pub service_charges: Option<FieldConfig>,
+  pub deposit: Option<FieldConfig>,
+  pub tags: Option<FieldConfig>,
+}
+
+/// Describes where a value can be found within a search result.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FieldConfig {
+  /// Element within the result, the result itself is used if missing.
+  pub selector: Option<String>,
+  /// Attribute to read, the element's text is used if missing.
+  pub attribute: Option<String>,
+  /// Only keep the first match (or its first group) of this expression.
+  pub regex: Option<String>,
+  /// Remove all matches of this expression.
+  pub remove: Option<String>,
+}
+
+/// Definition of a crawler that reads the listings from JSON embedded into the result page
+/// (see `JsonStateCrawler`).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonStateConfig {
+  pub name: String,
+  pub script: ScriptConfig,
+  /// Path to the array of listings within the embedded JSON.
+  /// If missing, every script matching the selector contains a single listing.
+  pub results: Option<String>,
+  #[serde(default = "default_encoding")]
+  pub encoding: String,
+  /// How numbers given as text are written on the portal: de, at, ch or en.
+  #[serde(default = "default_locale")]
+  pub locale: String,
+  /// Currency of the prices on the portal: EUR or CHF.
+  #[serde(default)]
+  pub currency: Currency,
+  /// Url of a property, where `{externalid}` will be replaced by the property's id.
+  /// Only used, if no url field is configured.
+  pub url: Option<String>,
+  pub fields: JsonFieldsConfig,
+}
+
+/// Describes where the JSON can be found within the result page.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScriptConfig {
+  #[serde(default = "default_script_selector")]
+  pub selector: String,
+  /// Only parse the first match (or its first group) of this expression within the script,
+  /// eg. when the JSON is assigned to a variable.
+  pub regex: Option<String>,
+}
+
+/// Paths to the values of a listing, eg. `price.value` or `images[0].url`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonFieldsConfig {
+  pub externalid: String,
+  pub title: String,
+  pub address: String,
+  /// Cold rent or purchase price, depending on the watcher's contract type.
+  pub price: String,
+  pub squaremeters: String,
+  pub rooms: String,
+  pub url: Option<String>,
+  pub plot_squaremeters: Option<String>,
+  pub warm_rent: Option<String>,
+  pub service_charges: Option<String>,
+  pub deposit: Option<String>,
+  pub tags: Option<String>,
+}
+
+fn default_script_selector() -> String {
+  String::from("script")
+}
+
+fn default_locale() -> String {
+  String::from("de")
+}
+
+fn default_encoding() -> String {
+  String::from("utf-8")
 }
@@ -1,48 +1,268 @@
 extern crate encoding_rs;
 extern crate kuchiki;
-extern crate regex;
 extern crate reqwest;
 extern crate std;
 
-use self::regex::Regex;
-use crate::models::Encoding;
+use crate::crawlers::quantity::{self, Locale, Quantity};
+use crate::crawlers::Config;
 use crate::models::PropertyData;
-use kuchiki::{ElementData, NodeDataRef};
+use chrono::{DateTime, Local, NaiveDate};
+use kuchiki::{ElementData, NodeDataRef, NodeRef};
+use lazy_static::lazy_static;
+use regex::Regex;
 use std::ops::Deref;
+use url::Url;
 
 #[derive(Debug)]
-pub struct Error {
-  pub message: String,
+pub enum Error {
+  /// the portal could not be reached
+  Network(reqwest::Error),
+  /// the portal answered with an unsuccessful status code
+  Status {
+    url: String,
+    status: reqwest::StatusCode,
+  },
+  /// the portal served a block or captcha page instead of the requested one
+  Blocked { url: String, reason: String },
+  /// the response could not be read as a document
+  Decoding(std::io::Error),
+  /// a recorded response could not be read or written
+  Fixture(std::io::Error),
+  /// the crawler's main selector did not match the document
+  MainSelector(String),
+  /// the selector or attribute of a single field did not match a result
+  FieldSelector {
+    selector: Option<String>,
+    attribute: Option<String>,
+  },
+  /// the text of a field does not contain a number
+  Number { value: String },
+  /// a result lacks information required for a property
+  Incomplete(String),
+  /// a result is deliberately not turned into a property
+  Skipped(String),
+  /// no crawler has been registered with the watcher's name
+  UnknownCrawler(String),
+  /// the address of a page is not a valid url
+  InvalidUrl(String),
+  /// data embedded into the page is not valid JSON
+  Json(serde_json::Error),
+  /// the watcher did not finish within the given number of seconds
+  Timeout(u64),
 }
 
-impl From<std::num::ParseFloatError> for Error {
-  fn from(_err: std::num::ParseFloatError) -> Error {
-    return Error {
-      message: "Could not parse float!".to_owned(),
-    };
+impl Error {
+  /// Short name of the error's kind, used to aggregate errors per watcher.
+  pub fn kind(&self) -> &'static str {
+    match self {
+      Error::Network(_) => "network",
+      Error::Status { .. } => "http status",
+      Error::Blocked { .. } => "blocked",
+      Error::Decoding(_) => "decoding",
+      Error::Fixture(_) => "fixture",
+      Error::MainSelector(_) => "main selector",
+      Error::FieldSelector { .. } => "field selector",
+      Error::Number { .. } => "number",
+      Error::Incomplete(_) => "incomplete data",
+      Error::Skipped(_) => "skipped",
+      Error::UnknownCrawler(_) => "unknown crawler",
+      Error::InvalidUrl(_) => "invalid url",
+      Error::Json(_) => "json",
+      Error::Timeout(_) => "timeout",
+    }
+  }
+
+  pub fn field_selector(selector: &str) -> Error {
+    Error::FieldSelector {
+      selector: Some(selector.to_owned()),
+      attribute: None,
+    }
+  }
+}
+
+impl std::fmt::Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Error::Network(e) => write!(f, "Request Error: {}", e),
+      Error::Status { url, status } => write!(f, "'{}' answered with status {}!", url, status),
+      Error::Blocked { url, reason } => write!(f, "'{}' seems to be blocked: {}", url, reason),
+      Error::Decoding(e) => write!(f, "Document could not be decoded: {}", e),
+      Error::Fixture(e) => write!(f, "Fixture could not be accessed: {}", e),
+      Error::MainSelector(selector) => write!(f, "Main selector '{}' did not match.", selector),
+      Error::FieldSelector {
+        selector: Some(selector),
+        attribute: Some(attribute),
+      } => write!(
+        f,
+        "Could not find attribute '{}' of selector '{}'!",
+        attribute, selector
+      ),
+      Error::FieldSelector {
+        attribute: Some(attribute),
+        ..
+      } => write!(f, "Could not find attribute '{}'!", attribute),
+      Error::FieldSelector { selector, .. } => write!(
+        f,
+        "Could not find selector '{}'!",
+        selector.as_deref().unwrap_or_default()
+      ),
+      Error::Number { value, .. } => write!(f, "No number found in '{}'!", value),
+      Error::Incomplete(message) => write!(f, "Information is incomplete: {}", message),
+      Error::Skipped(reason) => write!(f, "Skipped: {}", reason),
+      Error::UnknownCrawler(name) => write!(f, "Could not find crawler with name: {}", name),
+      Error::InvalidUrl(url) => write!(f, "'{}' is not a valid url!", url),
+      Error::Json(e) => write!(f, "Embedded data could not be parsed: {}", e),
+      Error::Timeout(seconds) => write!(f, "Watcher did not finish within {} seconds!", seconds),
+    }
+  }
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Error::Network(e) => Some(e),
+      Error::Decoding(e) | Error::Fixture(e) => Some(e),
+      Error::Json(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<reqwest::Error> for Error {
+  fn from(err: reqwest::Error) -> Error {
+    Error::Network(err)
   }
 }
 
 pub struct Metadata {
   pub name: String,
-  pub encoding: Encoding,
+  /// encoding of the portal's pages, if they do not declare their charset
+  pub encoding: &'static encoding_rs::Encoding,
+}
+
+/// Describes the result page that is being transformed into properties.
+pub struct Context<'a> {
+  /// watcher that requested the page
+  pub config: &'a Config,
+  /// url the page has been loaded from
+  pub url: &'a Url,
+  pub page: u32,
+  pub fetched: DateTime<Local>,
+}
+
+impl<'a> Context<'a> {
+  /// Resolves a link found on the page into an absolute url.
+  pub fn absolute_url(&self, link: &str) -> Result<String, Error> {
+    self
+      .url
+      .join(link)
+      .map(|url| url.to_string())
+      .map_err(|_| Error::InvalidUrl(link.to_owned()))
+  }
 }
 
 fn sanitize(str: &String) -> String {
   str.replace("\n", "").replace("\r", "")
 }
 
+/// Returns the given url with query parameter `name` set to `value`.
+/// Other query parameters are kept in their original order.
+pub fn with_query_param(url: &Url, name: &str, value: &str) -> String {
+  let pairs: Vec<(String, String)> = url
+    .query_pairs()
+    .filter(|(key, _)| key != name)
+    .map(|(key, val)| (key.into_owned(), val.into_owned()))
+    .collect();
+  let mut next = url.clone();
+  next
+    .query_pairs_mut()
+    .clear()
+    .extend_pairs(pairs)
+    .append_pair(name, value);
+  next.to_string()
+}
+
+/// Stores a price in the field of the property that matches its label on the portal,
+/// eg. "Kaltmiete", "Warmmiete", "Nebenkosten", "Kaution" or "Kaufpreis".
+/// Prices without a known label are taken as cold rent.
+/// Labels naming a rent are rents, even if they mention service charges, eg. "Kaltmiete zzgl. Nebenkosten".
+pub fn assign_price(data: &mut PropertyData, label: &str, price: f32) {
+  let label = label.to_lowercase();
+  let field = if label.contains("kauf") || label.contains("purchase") {
+    &mut data.purchase_price
+  } else if label.contains("warm") || label.contains("gesamt") {
+    &mut data.warm_rent
+  } else if label.contains("kalt") || label.contains("miete") {
+    &mut data.cold_rent
+  } else if label.contains("neben") {
+    &mut data.service_charges
+  } else if label.contains("kaution") {
+    &mut data.deposit
+  } else {
+    &mut data.cold_rent
+  };
+  *field = Some(price);
+}
+
 pub trait Crawler: Send + Sync {
   fn metadata(&self) -> Metadata;
 
-  fn selector(&self) -> &'static str;
+  fn selector(&self) -> &str;
+
+  /// Extracts a property from a single search result.
+  /// The contract and property type are taken from the watcher, unless the result states them.
+  fn transform_result(
+    &self,
+    context: &Context,
+    result: NodeDataRef<ElementData>,
+  ) -> Result<PropertyData, Error>;
+
+  /// Extracts all properties from a result page.
+  /// By default, every element matching the crawler's selector is transformed by `transform_result`.
+  fn transform_document(
+    &self,
+    context: &Context,
+    document: &NodeRef,
+  ) -> Result<Vec<Result<PropertyData, Error>>, Error> {
+    match document.select(self.selector()) {
+      Ok(results) => Ok(
+        results
+          .map(|result| self.transform_result(context, result))
+          .collect(),
+      ),
+      Err(()) => Err(Error::MainSelector(self.selector().to_owned())),
+    }
+  }
+
+  /// Whether properties of this crawler can be completed from their detail page (expose).
+  fn has_expose(&self) -> bool {
+    false
+  }
 
-  fn transform_result(&self, result: NodeDataRef<ElementData>) -> Result<PropertyData, Error>;
+  /// Completes the property with the information found on the expose, that has been loaded from the property's url.
+  fn transform_expose(
+    &self,
+    _expose: NodeDataRef<ElementData>,
+    _data: &mut PropertyData,
+  ) -> Result<(), Error> {
+    Ok(())
+  }
+
+  /// Returns the url of the result page following `page`, which has been loaded from `url`.
+  /// By default, a `rel="next"` link within the document will be followed.
+  fn next_page(&self, document: &NodeRef, url: &Url, _page: u32) -> Option<String> {
+    document
+      .select_first("a[rel=next], link[rel=next]")
+      .ok()
+      .and_then(|link| link.attributes.borrow().get("href").map(String::from))
+      .and_then(|href| url.join(&href).ok())
+      .map(|next| next.to_string())
+  }
 
   fn get_attr(
     element: &NodeDataRef<ElementData>,
-    select_opt: Option<&'static str>,
-    name: &'static str,
+    select_opt: Option<&str>,
+    name: &str,
   ) -> Result<String, Error>
   where
     Self: Sized,
@@ -51,68 +271,169 @@ pub trait Crawler: Send + Sync {
       Some(select) => match element.as_node().select_first(select.as_ref()) {
         Ok(node) => match node.attributes.borrow().get(name) {
           Some(val) => Ok(sanitize(&val.to_string())),
-          None => Err(Error {
-            message: format!("Could not find attribute '{}'!", name),
+          None => Err(Error::FieldSelector {
+            selector: Some(select.to_owned()),
+            attribute: Some(name.to_owned()),
           }),
         },
-        Err(_e) => Err(Error {
-          message: format!("Could not find an element matching selector '{}'!", select),
-        }),
+        Err(_e) => Err(Error::field_selector(select)),
       },
       None => match element.deref().attributes.borrow_mut().get(name) {
         Some(val) => Ok(sanitize(&val.to_string())),
-        None => Err(Error {
-          message: format!("Could not find attribute '{}'!", name),
+        None => Err(Error::FieldSelector {
+          selector: None,
+          attribute: Some(name.to_owned()),
         }),
       },
     }
   }
 
-  fn get_text(result: &NodeDataRef<ElementData>, selector: &'static str) -> Result<String, Error>
+  fn get_text(result: &NodeDataRef<ElementData>, selector: &str) -> Result<String, Error>
   where
     Self: Sized,
   {
     match result.as_node().select_first(selector) {
       Ok(el) => Ok(sanitize(&el.text_contents())),
-      Err(()) => Err(Error {
-        message: format!("Could not find selector '{}'!", selector),
-      }),
+      Err(()) => Err(Error::field_selector(selector)),
     }
   }
 
-  fn get_texts(
-    result: &NodeDataRef<ElementData>,
-    selector: &'static str,
-  ) -> Result<Vec<String>, Error>
+  fn get_optional_text(result: &NodeDataRef<ElementData>, selector: &str) -> Option<String>
+  where
+    Self: Sized,
+  {
+    Self::get_text(result, selector)
+      .ok()
+      .map(|text| text.trim().to_string())
+      .filter(|text| !text.is_empty())
+  }
+
+  fn get_texts(result: &NodeDataRef<ElementData>, selector: &str) -> Result<Vec<String>, Error>
   where
     Self: Sized,
   {
     match result.as_node().select(selector) {
       Ok(elements) => Ok(elements.map(|e| sanitize(&e.text_contents())).collect()),
-      Err(()) => Err(Error {
-        message: format!("Could not find selector '{}'!", selector),
-      }),
+      Err(()) => Err(Error::field_selector(selector)),
     }
   }
 
+  /// Reads a quantity written the way of the portal, German by default.
+  fn parse_quantity(text: &str) -> Quantity
+  where
+    Self: Sized,
+  {
+    quantity::parse(text, Locale::De)
+  }
+
   fn parse_number(number_as_str: String) -> Result<f32, Error>
   where
     Self: Sized,
   {
-    let rent_regex = Regex::new(r"\d+(\.\d{3})*(,\d+)?").unwrap();
-    match rent_regex
-      .captures_iter(number_as_str.as_str())
-      .next()
-      .and_then(|capture| Some(capture[0].replace(".", "").replace(",", ".")))
-    {
-      Some(rent) => Ok(rent.parse()?),
-      None => Err(Error {
-        message: format!("No number found in '{}'!", number_as_str),
+    match Self::parse_quantity(&number_as_str).value() {
+      Some(number) => Ok(number),
+      None => Err(Error::Number {
+        value: number_as_str,
       }),
     }
   }
 
+  /// Reads the first date within a text like "ab 01.11.2020", "1.11.20" or "2020-11-01".
+  /// Dates like "sofort" or "nach Vereinbarung" are not read.
+  fn parse_date(text: &str) -> Option<NaiveDate>
+  where
+    Self: Sized,
+  {
+    lazy_static! {
+      static ref DATE_REGEX: Regex =
+        Regex::new(r"(\d{1,2})\.(\d{1,2})\.(\d{4}|\d{2})\b|(\d{4})-(\d{2})-(\d{2})").unwrap();
+    }
+    let captures = DATE_REGEX.captures(text)?;
+    let number = |group: usize| captures.get(group)?.as_str().parse::<u32>().ok();
+    match (number(1), number(2), number(3)) {
+      (Some(day), Some(month), Some(year)) => {
+        // two-digit years are from this century
+        let year = if year < 100 { year + 2000 } else { year };
+        NaiveDate::from_ymd_opt(year as i32, month, day)
+      }
+      _ => NaiveDate::from_ymd_opt(number(4)? as i32, number(5)?, number(6)?),
+    }
+  }
+
   fn log(&self, message: String) {
     println!("{}: {}", self.metadata().name, message);
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::{assign_price, with_query_param};
+  use crate::models::Property;
+  use url::Url;
+
+  #[test]
+  fn query_param_added() {
+    // GIVEN
+    let url =
+      Url::parse("https://www.immowelt.de/liste/muenchen/wohnungen/mieten?sort=relevanz").unwrap();
+
+    // WHEN
+    let next = with_query_param(&url, "cp", "2");
+
+    // THEN
+    assert_eq!(
+      next,
+      "https://www.immowelt.de/liste/muenchen/wohnungen/mieten?sort=relevanz&cp=2"
+    );
+  }
+
+  #[test]
+  fn query_param_replaced() {
+    // GIVEN
+    let url =
+      Url::parse("https://www.immobilienscout24.de/Suche?pagenumber=2&price=-1500.0").unwrap();
+
+    // WHEN
+    let next = with_query_param(&url, "pagenumber", "3");
+
+    // THEN
+    assert_eq!(
+      next,
+      "https://www.immobilienscout24.de/Suche?price=-1500.0&pagenumber=3"
+    );
+  }
+
+  #[test]
+  fn price_assigned_by_label() {
+    // GIVEN
+    let mut data = Property::dummy(0., 50., 2.).data.unwrap();
+    data.cold_rent = None;
+
+    // WHEN
+    assign_price(&mut data, "Warmmiete", 1150.);
+    assign_price(&mut data, "zzgl. Nebenkosten", 150.);
+    assign_price(&mut data, "Kaution", 3000.);
+
+    // THEN
+    assert_eq!(data.warm_rent, Some(1150.));
+    assert_eq!(data.service_charges, Some(150.));
+    assert_eq!(data.deposit, Some(3000.));
+    assert_eq!(data.cold_rent, None);
+    assert_eq!(data.price(), Some(1000.));
+  }
+
+  #[test]
+  fn rent_assigned_despite_service_charges_in_label() {
+    // GIVEN
+    let mut data = Property::dummy(0., 50., 2.).data.unwrap();
+    data.cold_rent = None;
+
+    // WHEN
+    assign_price(&mut data, "Kaltmiete zzgl. Nebenkosten", 1290.);
+
+    // THEN
+    assert_eq!(data.cold_rent, Some(1290.));
+    assert_eq!(data.service_charges, None);
+    assert_eq!(data.price(), Some(1290.));
+  }
+}
@@ -4,105 +4,318 @@ extern crate regex;
 extern crate reqwest;
 extern crate std;
 
-use crate::crawlers::{Config, Crawler, Error as CrawlingError};
-use crate::models::{Encoding, Property};
-use kuchiki::iter::*;
+use crate::configuration::{ApplicationConfig, BlockingConfig};
+use crate::crawlers::fixtures::{self, FixtureMode};
+use crate::crawlers::{charset, Config, Context, Crawler, Error, Health, HttpClient};
+use crate::filters::{self, Filter};
+use crate::models::{ContractType, Property, PropertyData};
+use chrono::{DateTime, Local};
 use kuchiki::traits::*;
-use reqwest::Response;
+use reqwest::StatusCode;
 use std::time::Instant;
+use tokio::sync::Mutex;
+use url::Url;
 
-#[derive(Debug)]
-pub struct Error {
-  pub message: String,
-}
-
-impl From<CrawlingError> for Error {
-  fn from(err: CrawlingError) -> Error {
-    return Error {
-      message: format!("Crawler Error: {}", err.message),
+pub async fn execute(
+  app_config: &ApplicationConfig,
+  http: &HttpClient,
+  config: &Config,
+  crawler: &Box<dyn Crawler>,
+  filters: &Mutex<Vec<Box<dyn Filter>>>,
+  health: &mut Health,
+) -> Result<Vec<Property>, Error> {
+  let crawler = crawler.as_ref();
+  let encoding = config.encoding.as_deref().and_then(charset::for_label);
+  let mut properties: Vec<Property> = Vec::new();
+  let mut url = config.address.to_owned();
+  let mut page = 1;
+  loop {
+    let page_result = match get_page(app_config, http, &url, crawler, encoding).await {
+      Ok(body) => match detect_block(&app_config.blocking, &body) {
+        Some(reason) => Err(Error::Blocked {
+          url: url.to_owned(),
+          reason,
+        }),
+        None => parse_page(config, crawler, &body, &url, page, Local::now(), health),
+      },
+      Err(e) => Err(e),
     };
-  }
-}
+    let (page_properties, next_url) = match page_result {
+      Ok(page_result) => page_result,
+      // keep what has been found on the previous pages
+      Err(e) if page > 1 => {
+        crawler.log(format!("could not process page {}: {}", page, e));
+        health.count(&e);
+        break;
+      }
+      Err(e) => return Err(e),
+    };
+    health.pages = page;
+
+    let found = page_properties.len();
+    crawler.log(format!("found {} properties on page {}.", found, page));
 
-impl From<std::io::Error> for Error {
-  fn from(_err: std::io::Error) -> Error {
-    return Error {
-      message: "IO Error".to_owned(),
+    // results are expected to be sorted by date, so there is nothing new to come
+    let next_url = match next_url {
+      Some(next)
+        if config.incremental
+          && found > 0
+          && filters::all_known(app_config, filters, &page_properties).await =>
+      {
+        crawler.log(format!(
+          "all properties on page {} are known, will not load {}.",
+          page, next
+        ));
+        None
+      }
+      next_url => next_url,
     };
-  }
-}
+    for property in page_properties {
+      // listings may move to the next page, while we are paginating
+      if !properties.iter().any(|known| known.id() == property.id()) {
+        properties.push(property);
+      }
+    }
 
-impl From<reqwest::Error> for Error {
-  fn from(_err: reqwest::Error) -> Error {
-    let mut message = String::from("Request Error: ");
-    message.push_str(_err.to_string().as_str());
-    return Error { message };
+    match next_url {
+      Some(next) if found > 0 && next != url => {
+        url = next;
+        page += 1;
+      }
+      _ => break,
+    }
   }
+  Ok(properties)
 }
 
-pub async fn execute(config: &Config, crawler: &Box<dyn Crawler>) -> Result<Vec<Property>, Error> {
-  let results = get_results(config, crawler).await?;
+fn transform_results(
+  context: &Context,
+  crawler: &dyn Crawler,
+  results: Vec<Result<PropertyData, Error>>,
+  health: &mut Health,
+) -> Vec<Property> {
   let mut successful: Vec<Property> = Vec::new();
   let flat_results: Vec<Result<Property, Error>> = results
+    .into_iter()
     .map(|result| {
-      let flat = Property::new(crawler.metadata().name.to_owned(), config.city.clone());
-      let mut data = crawler.transform_result(result)?;
-      data.contract_type = config.contract_type.to_owned();
-      data.property_type = config.property_type.to_owned();
+      let mut flat = Property::new(
+        crawler.metadata().name.to_owned(),
+        context.config.city.clone(),
+      );
+      // properties are seen when their page has been fetched, not when it is parsed
+      flat.date = context.fetched.timestamp();
+      let mut data = result?;
+      // crawlers take unlabeled prices as rent, which is the purchase price of an offer to buy
+      if data.contract_type == ContractType::Buy && data.purchase_price.is_none() {
+        data.purchase_price = data.cold_rent.take().or_else(|| data.warm_rent.take());
+      }
       Ok(flat.fill(&data))
     })
     .collect();
   for flat_result in flat_results {
+    health.results += 1;
     match flat_result {
-      Ok(flat) => successful.push(flat),
-      Err(e) => println!(
-        "Could not process flat within crawler '{}', because: {}",
-        crawler.metadata().name,
-        e.message
-      ),
+      Ok(flat) => {
+        health.parsed += 1;
+        successful.push(flat)
+      }
+      Err(e) => {
+        println!(
+          "Could not process flat on page {} within crawler '{}', because: {}",
+          context.page,
+          crawler.metadata().name,
+          e
+        );
+        health.add_failure(&e);
+      }
     }
   }
-  Ok(successful)
+  successful
 }
 
-async fn decode_response(response: Response, encoding: &Encoding) -> Result<String, Error> {
-  let buf: Vec<u8> = response.bytes().await.unwrap().to_vec();
-  let (encoded_string, _, _) = match encoding {
-    Encoding::Latin1 => encoding_rs::ISO_8859_2.decode(&buf),
-    Encoding::Utf8 => encoding_rs::UTF_8.decode(&buf),
+/// Completes the given property, found by the given watcher, with the details found on its expose.
+/// Exposes are not checked for block pages, as they often embed captchas in their contact forms,
+/// and a failed expose does not count towards the health of the watcher or pause its portal.
+pub async fn fetch_expose(
+  app_config: &ApplicationConfig,
+  http: &HttpClient,
+  config: &Config,
+  crawler: &dyn Crawler,
+  property: &Property,
+) -> Result<Property, Error> {
+  let mut data = match property.data {
+    Some(ref data) => data.clone(),
+    None => return Err(Error::Incomplete(String::from("property has no data"))),
   };
-  Ok(encoded_string.into())
+
+  let encoding = config.encoding.as_deref().and_then(charset::for_label);
+  let body = get_page(app_config, http, &data.url, crawler, encoding).await?;
+  let document = kuchiki::parse_html()
+    .from_utf8()
+    .read_from(&mut body.as_bytes())
+    .map_err(Error::Decoding)?;
+  match document.select_first("html") {
+    Ok(expose) => crawler.transform_expose(expose, &mut data)?,
+    Err(()) => return Err(Error::MainSelector(String::from("html"))),
+  }
+  Ok(property.fill(&data))
 }
 
-async fn get_results(
-  config: &Config,
-  crawler: &Box<dyn Crawler>,
-) -> Result<Select<Elements<Descendants>>, Error> {
-  let url = config.address.to_owned();
+async fn get_page(
+  app_config: &ApplicationConfig,
+  http: &HttpClient,
+  url: &str,
+  crawler: &dyn Crawler,
+  encoding: Option<&'static encoding_rs::Encoding>,
+) -> Result<String, Error> {
+  let fixtures = &app_config.fixtures;
+  let name = crawler.metadata().name;
+  let (body, content_type) = if fixtures.mode == FixtureMode::Replay {
+    crawler.log(format!("<< replaying recorded response of url '{}'.", url));
+    fixtures::replay(&fixtures.directory, &name, url).map_err(Error::Fixture)?
+  } else {
+    let request_start = Instant::now();
+    crawler.log(format!(">> sending request to url '{}' ... ", url));
+    let response = match http.get(url).await {
+      Err(Error::Status { url, status })
+        if status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS =>
+      {
+        return Err(Error::Blocked {
+          url,
+          reason: format!("status {}", status),
+        })
+      }
+      result => result?,
+    };
+    crawler.log(format!(
+      "<< received response in {} ms.",
+      request_start.elapsed().as_millis()
+    ));
+    if fixtures.mode == FixtureMode::Record {
+      fixtures::record(
+        &fixtures.directory,
+        &name,
+        url,
+        &response.body,
+        response.content_type.as_deref(),
+      )
+      .map_err(Error::Fixture)?;
+    }
+    (response.body, response.content_type)
+  };
 
-  let request_start = Instant::now();
-  crawler.log(format!(">> sending request to url '{}' ... ", url));
-  let response = reqwest::get(url.as_str()).await?;
-  crawler.log(format!(
-    "<< received response in {} ms.",
-    request_start.elapsed().as_millis()
-  ));
+  Ok(charset::decode(
+    &body,
+    content_type.as_deref(),
+    encoding,
+    crawler.metadata().encoding,
+  ))
+}
+
+/// Recognizes block and captcha pages, that are served instead of the requested result page.
+fn detect_block(config: &BlockingConfig, body: &str) -> Option<String> {
+  if body.len() < config.min_body_size {
+    return Some(format!("response has only {} bytes", body.len()));
+  }
+  config
+    .markers
+    .iter()
+    .find(|marker| body.contains(marker.as_str()))
+    .map(|marker| format!("response contains '{}'", marker))
+}
 
+/// Extracts all properties from a result page and determines the url of the following page.
+fn parse_page(
+  config: &Config,
+  crawler: &dyn Crawler,
+  body: &str,
+  url: &str,
+  page: u32,
+  fetched: DateTime<Local>,
+  health: &mut Health,
+) -> Result<(Vec<Property>, Option<String>), Error> {
+  let parsed_url = Url::parse(url).map_err(|_| Error::InvalidUrl(url.to_owned()))?;
   let parsing_start = Instant::now();
   crawler.log(format!("parsing document ..."));
-  let decoded_response = decode_response(response, &crawler.metadata().encoding).await?;
   let document = kuchiki::parse_html()
     .from_utf8()
-    .read_from(&mut decoded_response.as_bytes())?;
+    .read_from(&mut body.as_bytes())
+    .map_err(Error::Decoding)?;
   crawler.log(format!(
     "document parsed successfully in {} ms.",
     parsing_start.elapsed().as_millis()
   ));
 
-  match document.select(crawler.selector()) {
-    Ok(nodes) => Ok(nodes),
-    Err(()) => Err(Error {
-      message: "Main selector did not match.".to_owned(),
-    }),
+  let context = Context {
+    config,
+    url: &parsed_url,
+    page,
+    fetched,
+  };
+  let results = crawler.transform_document(&context, &document)?;
+  let properties = transform_results(&context, crawler, results, health);
+
+  let next_url = if page < config.max_pages {
+    crawler.next_page(&document, &parsed_url, page)
+  } else {
+    None
+  };
+
+  Ok((properties, next_url))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::detect_block;
+  use crate::configuration::{ApplicationConfig, BlockingConfig};
+
+  fn config() -> BlockingConfig {
+    BlockingConfig {
+      min_body_size: 64,
+      markers: vec![String::from("g-recaptcha")],
+      cooldown: 900,
+      max_cooldown: 86400,
+    }
+  }
+
+  #[test]
+  fn detect_captcha_page() {
+    // GIVEN
+    let body =
+      r#"<html><body><h1>Ich bin kein Roboter</h1><div class="g-recaptcha"></div></body></html>"#;
+
+    // THEN
+    assert_eq!(
+      detect_block(&config(), body),
+      Some(String::from("response contains 'g-recaptcha'"))
+    );
+  }
+
+  #[test]
+  fn detect_empty_page() {
+    assert!(detect_block(&config(), "<html></html>").is_some());
+  }
+
+  #[test]
+  fn accept_result_page() {
+    // GIVEN
+    let body =
+      r#"<html><body><ul><li class="result">Schöne Wohnung in Schwabing</li></ul></body></html>"#;
+
+    // THEN
+    assert_eq!(detect_block(&config(), body), None);
+  }
+
+  #[test]
+  fn accept_embedded_captchas_by_default() {
+    // GIVEN
+    let mut app_config: ApplicationConfig = serde_json::from_str(r#"{ "watchers": [] }"#).unwrap();
+    app_config.blocking.min_body_size = 0;
+    let body = r#"<html><body><ul><li class="result">Schöne Wohnung in Schwabing</li></ul>
+      <form class="contact"><div class="g-recaptcha" data-sitekey="6Lc"></div></form>
+      <script src="/cdn-cgi/challenge-platform/scripts/jsd/main.js"></script></body></html>"#;
+
+    // THEN
+    assert_eq!(detect_block(&app_config.blocking, body), None);
   }
 }
@@ -0,0 +1,249 @@
+use serde_derive::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Whether responses of the portals are recorded to or replayed from the fixture directory.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub enum FixtureMode {
+  Off,
+  Record,
+  Replay,
+}
+
+impl std::str::FromStr for FixtureMode {
+  type Err = ();
+  fn from_str(s: &str) -> Result<Self, ()> {
+    match s {
+      "off" => Ok(FixtureMode::Off),
+      "record" => Ok(FixtureMode::Record),
+      "replay" => Ok(FixtureMode::Replay),
+      _ => Err(()),
+    }
+  }
+}
+
+/// Returns the file a response of `url` is recorded to.
+/// Responses are grouped by crawler and named by a hash of their url,
+/// so that every page of every watcher gets its own fixture.
+pub fn fixture_path(directory: &str, crawler: &str, url: &str) -> PathBuf {
+  Path::new(directory)
+    .join(crawler)
+    .join(format!("{:016x}.html", fnv1a(url.as_bytes())))
+}
+
+/// Returns the file the Content-Type header of a response is recorded to, next to its fixture.
+fn content_type_path(directory: &str, crawler: &str, url: &str) -> PathBuf {
+  fixture_path(directory, crawler, url).with_extension("content-type")
+}
+
+/// Records the body of a response and its Content-Type, which may name the charset of the body.
+pub fn record(
+  directory: &str,
+  crawler: &str,
+  url: &str,
+  body: &[u8],
+  content_type: Option<&str>,
+) -> std::io::Result<()> {
+  let path = fixture_path(directory, crawler, url);
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent)?;
+  }
+  std::fs::write(path, body)?;
+  let content_type_path = content_type_path(directory, crawler, url);
+  match content_type {
+    Some(content_type) => std::fs::write(content_type_path, content_type),
+    // a previous recording of the same url may have had one
+    None => match std::fs::remove_file(content_type_path) {
+      Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+      result => result,
+    },
+  }
+}
+
+/// Returns the recorded body of a response and its Content-Type, if one has been recorded.
+pub fn replay(
+  directory: &str,
+  crawler: &str,
+  url: &str,
+) -> std::io::Result<(Vec<u8>, Option<String>)> {
+  let body = std::fs::read(fixture_path(directory, crawler, url))?;
+  let content_type = match std::fs::read_to_string(content_type_path(directory, crawler, url)) {
+    Ok(content_type) => Some(content_type),
+    // responses without a Content-Type, or recorded before it was recorded too
+    Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => None,
+    Err(e) => return Err(e),
+  };
+  Ok((body, content_type))
+}
+
+/// Hash that is stable across builds, other than the `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+  bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+    (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+  })
+}
+
+/// Runs the crawler on the checked-in result page `tests/fixtures/<name>/results.html`,
+/// as if it had been loaded from `address` by a watcher for flats to rent in München,
+/// and compares the extracted properties, or the error of each rejected result, with
+/// `tests/fixtures/<name>/results.json`.
+/// Set `UPDATE_SNAPSHOTS=1` to overwrite the expected properties instead.
+#[cfg(test)]
+pub fn assert_snapshot(crawler: &dyn crate::crawlers::Crawler, name: &str, address: &str) {
+  use crate::crawlers::{Config, Context};
+  use crate::models::{ContractType, PropertyType};
+  use kuchiki::traits::*;
+
+  let directory = Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("tests")
+    .join("fixtures")
+    .join(name);
+  let body = std::fs::read(directory.join("results.html")).unwrap();
+  let decoded = crate::crawlers::charset::decode(&body, None, None, crawler.metadata().encoding);
+  let document = kuchiki::parse_html().one(decoded);
+  let config = Config {
+    address: address.to_owned(),
+    city: String::from("München"),
+    crawler: name.to_owned(),
+    property_type: PropertyType::Flat,
+    contract_type: ContractType::Rent,
+    max_pages: 1,
+    incremental: true,
+    encoding: None,
+    interval: None,
+    cron: None,
+    jitter: 0,
+    quiet_hours: None,
+  };
+  let url = url::Url::parse(address).unwrap();
+  let context = Context {
+    config: &config,
+    url: &url,
+    page: 1,
+    // a fixed time keeps relative dates such as "Heute, 14:32" stable
+    fetched: chrono::DateTime::parse_from_rfc3339("2026-10-17T12:00:00+02:00")
+      .unwrap()
+      .with_timezone(&chrono::Local),
+  };
+  let properties: Vec<serde_json::Value> = crawler
+    .transform_document(&context, &document)
+    .unwrap()
+    .into_iter()
+    .map(|result| match result {
+      Ok(data) => {
+        let mut value = serde_json::to_value(&data).unwrap();
+        // tags are not serialized for observers, but belong to what a crawler extracts
+        if !data.tags.is_empty() {
+          value["tags"] = serde_json::to_value(&data.tags).unwrap();
+        }
+        value
+      }
+      // results the crawler rejects are part of the snapshot, so none of them goes missing unnoticed
+      Err(e) => serde_json::json!({ "error": e.to_string() }),
+    })
+    .collect();
+  let actual = serde_json::Value::Array(properties);
+  compare_snapshot(&directory.join("results.json"), actual);
+}
+
+/// Runs the crawler on the checked-in expose `tests/fixtures/<name>/expose.html` of a flat to rent,
+/// whose search result only stated its cold rent, and compares the completed property with
+/// `tests/fixtures/<name>/expose.json`.
+/// Set `UPDATE_SNAPSHOTS=1` to overwrite the expected property instead.
+#[cfg(test)]
+pub fn assert_expose_snapshot(crawler: &dyn crate::crawlers::Crawler, name: &str) {
+  use crate::models::Property;
+  use kuchiki::traits::*;
+
+  let directory = Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("tests")
+    .join("fixtures")
+    .join(name);
+  let body = std::fs::read(directory.join("expose.html")).unwrap();
+  let decoded = crate::crawlers::charset::decode(&body, None, None, crawler.metadata().encoding);
+  let document = kuchiki::parse_html().one(decoded);
+  let mut data = Property::dummy(1290.0, 64.0, 2.0).data.unwrap();
+  crawler
+    .transform_expose(document.select_first("html").unwrap(), &mut data)
+    .unwrap();
+  compare_snapshot(
+    &directory.join("expose.json"),
+    serde_json::to_value(&data).unwrap(),
+  );
+}
+
+#[cfg(test)]
+fn compare_snapshot(snapshot: &Path, actual: serde_json::Value) {
+  if std::env::var("UPDATE_SNAPSHOTS").is_ok() {
+    std::fs::write(
+      snapshot,
+      serde_json::to_string_pretty(&actual).unwrap() + "\n",
+    )
+    .unwrap();
+  }
+  let expected: serde_json::Value =
+    serde_json::from_slice(&std::fs::read(snapshot).unwrap()).unwrap();
+  assert_eq!(actual, expected);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{fixture_path, record, replay};
+  use std::path::Path;
+
+  #[test]
+  fn replay_recorded_content_type() {
+    // GIVEN
+    let directory =
+      std::env::temp_dir().join(format!("properwatcher-fixtures-{}", std::process::id()));
+    let directory = directory.to_str().unwrap();
+    let url = "https://www.example.com/liste";
+    let body = "<html><body>Wohnung in München</body></html>".as_bytes();
+
+    // WHEN
+    record(
+      directory,
+      "generic",
+      url,
+      body,
+      Some("text/html; charset=iso-8859-1"),
+    )
+    .unwrap();
+    let with_content_type = replay(directory, "generic", url).unwrap();
+    record(directory, "generic", url, body, None).unwrap();
+    let without_content_type = replay(directory, "generic", url).unwrap();
+
+    // THEN
+    std::fs::remove_dir_all(directory).unwrap();
+    assert_eq!(with_content_type.0, body);
+    assert_eq!(
+      with_content_type.1,
+      Some(String::from("text/html; charset=iso-8859-1"))
+    );
+    assert_eq!(without_content_type.1, None);
+  }
+
+  #[test]
+  fn fixture_path_is_stable() {
+    // GIVEN
+    let url = "https://www.immobilienscout24.de/Suche/de/bayern/muenchen/wohnung-mieten";
+
+    // WHEN
+    let path = fixture_path("fixtures", "immoscout", url);
+
+    // THEN
+    assert_eq!(path, Path::new("fixtures/immoscout/a2760655a789dea0.html"));
+  }
+
+  #[test]
+  fn fixture_path_per_page() {
+    // GIVEN
+    let first_page = "https://www.immowelt.de/liste/muenchen/wohnungen/mieten";
+    let second_page = "https://www.immowelt.de/liste/muenchen/wohnungen/mieten?cp=2";
+
+    // THEN
+    assert_ne!(
+      fixture_path("fixtures", "immowelt", first_page),
+      fixture_path("fixtures", "immowelt", second_page)
+    );
+  }
+}
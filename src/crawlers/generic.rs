@@ -0,0 +1,330 @@
+extern crate kuchiki;
+extern crate regex;
+extern crate std;
+
+use super::{Context, Crawler, Error};
+use crate::crawlers::config::{FieldConfig, GenericConfig};
+use crate::crawlers::quantity::{self, Locale};
+use crate::crawlers::{charset, Metadata};
+use crate::models::{Currency, PropertyData};
+use kuchiki::{ElementData, NodeDataRef};
+use regex::Regex;
+
+struct Field {
+  selector: Option<String>,
+  attribute: Option<String>,
+  regex: Option<Regex>,
+  remove: Option<Regex>,
+}
+
+impl Field {
+  fn new(config: &FieldConfig) -> Result<Self, String> {
+    let compile = |pattern: &Option<String>| match pattern {
+      Some(pattern) => Regex::new(pattern)
+        .map(Some)
+        .map_err(|e| format!("invalid expression '{}': {}", pattern, e)),
+      None => Ok(None),
+    };
+    Ok(Field {
+      selector: config.selector.clone(),
+      attribute: config.attribute.clone(),
+      regex: compile(&config.regex)?,
+      remove: compile(&config.remove)?,
+    })
+  }
+}
+
+/// Crawler that is completely defined by a `[[crawler]]` section of the configuration.
+pub struct GenericCrawler {
+  name: String,
+  selector: String,
+  encoding: &'static encoding_rs::Encoding,
+  locale: Locale,
+  currency: Currency,
+  url: String,
+  externalid: Field,
+  title: Field,
+  address: Field,
+  price: Field,
+  squaremeters: Field,
+  rooms: Field,
+  plot_squaremeters: Option<Field>,
+  warm_rent: Option<Field>,
+  service_charges: Option<Field>,
+  deposit: Option<Field>,
+  tags: Option<Field>,
+}
+
+impl GenericCrawler {
+  pub fn new(config: &GenericConfig) -> Result<Self, String> {
+    let fields = &config.fields;
+    Ok(GenericCrawler {
+      name: config.name.clone(),
+      selector: config.selector.clone(),
+      encoding: charset::for_label(&config.encoding)
+        .ok_or_else(|| format!("unknown encoding '{}'", config.encoding))?,
+      locale: config
+        .locale
+        .parse()
+        .map_err(|_| format!("unknown locale '{}'", config.locale))?,
+      currency: config.currency,
+      url: config.url.clone(),
+      externalid: Field::new(&fields.externalid)?,
+      title: Field::new(&fields.title)?,
+      address: Field::new(&fields.address)?,
+      price: Field::new(&fields.price)?,
+      squaremeters: Field::new(&fields.squaremeters)?,
+      rooms: Field::new(&fields.rooms)?,
+      plot_squaremeters: match fields.plot_squaremeters {
+        Some(ref field) => Some(Field::new(field)?),
+        None => None,
+      },
+      warm_rent: match fields.warm_rent {
+        Some(ref field) => Some(Field::new(field)?),
+        None => None,
+      },
+      service_charges: match fields.service_charges {
+        Some(ref field) => Some(Field::new(field)?),
+        None => None,
+      },
+      deposit: match fields.deposit {
+        Some(ref field) => Some(Field::new(field)?),
+        None => None,
+      },
+      tags: match fields.tags {
+        Some(ref field) => Some(Field::new(field)?),
+        None => None,
+      },
+    })
+  }
+
+  fn get_value(result: &NodeDataRef<ElementData>, field: &Field) -> Result<String, Error> {
+    let raw = match (&field.selector, &field.attribute) {
+      (selector, Some(attribute)) => Self::get_attr(result, selector.as_deref(), attribute)?,
+      (Some(selector), None) => Self::get_text(result, selector)?,
+      (None, None) => result.text_contents(),
+    };
+    Self::clean(&raw, field)
+  }
+
+  fn get_values(result: &NodeDataRef<ElementData>, field: &Field) -> Vec<String> {
+    match (&field.selector, &field.attribute) {
+      (Some(selector), None) => Self::get_texts(result, selector)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|text| Self::clean(text, field).ok())
+        .filter(|text| !text.is_empty())
+        .collect(),
+      _ => Self::get_value(result, field).into_iter().collect(),
+    }
+  }
+
+  fn get_number(&self, result: &NodeDataRef<ElementData>, field: &Field) -> Result<f32, Error> {
+    let value = Self::get_value(result, field)?;
+    match quantity::parse(&value, self.locale).value() {
+      Some(number) => Ok(number),
+      None => Err(Error::Number { value }),
+    }
+  }
+
+  fn clean(value: &str, field: &Field) -> Result<String, Error> {
+    let extracted = match field.regex {
+      Some(ref regex) => match regex.captures(value) {
+        Some(captures) => captures
+          .get(1)
+          .or_else(|| captures.get(0))
+          .map(|capture| capture.as_str().to_owned())
+          .unwrap_or_default(),
+        None => {
+          return Err(Error::Incomplete(format!(
+            "'{}' does not match '{}'!",
+            value,
+            regex.as_str()
+          )))
+        }
+      },
+      None => value.to_owned(),
+    };
+    let cleaned = match field.remove {
+      Some(ref remove) => remove.replace_all(&extracted, "").into_owned(),
+      None => extracted,
+    };
+    Ok(cleaned.trim().to_owned())
+  }
+}
+
+impl Crawler for GenericCrawler {
+  fn metadata(&self) -> Metadata {
+    Metadata {
+      name: self.name.clone(),
+      encoding: self.encoding,
+    }
+  }
+
+  fn selector(&self) -> &str {
+    &self.selector
+  }
+
+  fn transform_result(
+    &self,
+    context: &Context,
+    result: NodeDataRef<ElementData>,
+  ) -> Result<PropertyData, Error> {
+    let externalid = Self::get_value(&result, &self.externalid)?;
+    let plot_squaremeters = self
+      .plot_squaremeters
+      .as_ref()
+      .and_then(|field| self.get_number(&result, field).ok());
+    let optional_number = |field: &Option<Field>| {
+      field
+        .as_ref()
+        .and_then(|field| self.get_number(&result, field).ok())
+    };
+    let tags = self
+      .tags
+      .as_ref()
+      .map(|field| Self::get_values(&result, field))
+      .unwrap_or_default();
+    Ok(PropertyData {
+      cold_rent: Some(self.get_number(&result, &self.price)?),
+      warm_rent: optional_number(&self.warm_rent),
+      service_charges: optional_number(&self.service_charges),
+      deposit: optional_number(&self.deposit),
+      purchase_price: None,
+      currency: self.currency,
+      squaremeters: self.get_number(&result, &self.squaremeters)?,
+      plot_squaremeters,
+      address: Self::get_value(&result, &self.address)?,
+      title: Self::get_value(&result, &self.title)?,
+      rooms: self.get_number(&result, &self.rooms)?,
+      url: context.absolute_url(&self.url.replace("{externalid}", &externalid))?,
+      externalid,
+      property_type: context.config.property_type.clone(),
+      contract_type: context.config.contract_type.clone(),
+      tags,
+      location: None,
+      available_from: None,
+      available_until: None,
+      room: None,
+      details: None,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::GenericCrawler;
+  use crate::crawlers::config::{FieldConfig, FieldsConfig, GenericConfig};
+  use crate::crawlers::{Config, Context, Crawler};
+  use crate::models::{ContractType, Currency, PropertyType};
+  use kuchiki::traits::*;
+  use url::Url;
+
+  fn field(selector: Option<&str>, attribute: Option<&str>) -> FieldConfig {
+    FieldConfig {
+      selector: selector.map(String::from),
+      attribute: attribute.map(String::from),
+      regex: None,
+      remove: None,
+    }
+  }
+
+  fn config() -> GenericConfig {
+    GenericConfig {
+      name: String::from("regional"),
+      selector: String::from(".offer"),
+      encoding: String::from("utf8"),
+      locale: String::from("de"),
+      currency: Currency::Eur,
+      url: String::from("/expose/{externalid}"),
+      fields: FieldsConfig {
+        externalid: FieldConfig {
+          regex: Some(String::from(r"offer-(\d+)")),
+          ..field(None, Some("id"))
+        },
+        title: field(Some("h2"), None),
+        address: FieldConfig {
+          remove: Some(String::from(r"\s*\([^)]*\)")),
+          ..field(Some(".address"), None)
+        },
+        price: field(Some(".price"), None),
+        squaremeters: field(Some(".size"), Some("data-value")),
+        rooms: field(Some(".rooms"), None),
+        plot_squaremeters: None,
+        warm_rent: None,
+        service_charges: Some(field(Some(".charges"), None)),
+        deposit: None,
+        tags: Some(field(Some(".tags li"), None)),
+      },
+    }
+  }
+
+  #[test]
+  fn transform_configured_result() {
+    // GIVEN
+    let crawler = GenericCrawler::new(&config()).ok().unwrap();
+    let watcher = Config {
+      address: String::from("https://www.example.com/suche/muenchen?kaufen=1"),
+      city: String::from("München"),
+      crawler: String::from("regional"),
+      property_type: PropertyType::House,
+      contract_type: ContractType::Buy,
+      max_pages: 1,
+      incremental: true,
+      encoding: None,
+      interval: None,
+      cron: None,
+      jitter: 0,
+      quiet_hours: None,
+    };
+    let url = Url::parse(&watcher.address).unwrap();
+    let context = Context {
+      config: &watcher,
+      url: &url,
+      page: 1,
+      fetched: chrono::Local::now(),
+    };
+    let document = kuchiki::parse_html().one(
+      r#"<div class="offer" id="offer-42">
+        <h2>Schöne Wohnung</h2>
+        <span class="address">Schwabing (Kreis), München</span>
+        <span class="price">1.250,50 €</span>
+        <span class="charges">zzgl. 180 € Nebenkosten</span>
+        <span class="size" data-value="65"></span>
+        <span class="rooms">2,5 Zimmer</span>
+        <ul class="tags"><li>Balkon</li><li> </li><li>Einbauküche</li></ul>
+      </div>"#,
+    );
+    let result = document.select_first(crawler.selector()).unwrap();
+
+    // WHEN
+    let data = crawler.transform_result(&context, result).ok().unwrap();
+
+    // THEN
+    assert_eq!(data.externalid, "42");
+    assert_eq!(data.url, "https://www.example.com/expose/42");
+    assert_eq!(data.title, "Schöne Wohnung");
+    assert_eq!(data.contract_type, ContractType::Buy);
+    assert_eq!(data.property_type, PropertyType::House);
+    assert_eq!(data.address, "Schwabing, München");
+    assert_eq!(data.cold_rent, Some(1250.5));
+    assert_eq!(data.service_charges, Some(180.0));
+    assert_eq!(data.total_rent(), Some(1430.5));
+    assert_eq!(data.squaremeters, 65.0);
+    assert_eq!(data.rooms, 2.5);
+    assert_eq!(data.tags, vec!["Balkon", "Einbauküche"]);
+  }
+
+  #[test]
+  fn reject_invalid_expression() {
+    // GIVEN
+    let mut config = config();
+    config.fields.price.regex = Some(String::from("("));
+
+    // WHEN
+    let crawler = GenericCrawler::new(&config);
+
+    // THEN
+    assert!(crawler.is_err());
+  }
+}
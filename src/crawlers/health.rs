@@ -0,0 +1,243 @@
+use crate::configuration::HealthConfig;
+use crate::crawlers::{Config, Error};
+use std::collections::HashMap;
+
+/// Statistics of a single watcher's run, used to detect portals that blocked us or changed their markup.
+#[derive(Debug, Default)]
+pub struct Health {
+  pub pages: u32,
+  /// number of search results matched by the crawler's main selector
+  pub results: usize,
+  /// number of search results that could be transformed into a property
+  pub parsed: usize,
+  /// number of errors by kind
+  pub errors: HashMap<&'static str, usize>,
+  /// number of failed transformations by reason
+  pub failures: HashMap<String, usize>,
+  /// error that stopped the watcher
+  pub error: Option<Error>,
+  /// reason, if the portal served a block page
+  pub blocked: Option<String>,
+}
+
+impl Health {
+  pub fn success_ratio(&self) -> f32 {
+    // results skipped on purpose do not indicate a broken crawler
+    let relevant = self.results - self.errors.get("skipped").cloned().unwrap_or(0);
+    if self.results == 0 {
+      0.0
+    } else if relevant == 0 {
+      1.0
+    } else {
+      self.parsed as f32 / relevant as f32
+    }
+  }
+
+  pub fn is_healthy(&self, config: &HealthConfig) -> bool {
+    self.error.is_none() && self.results > 0 && self.success_ratio() >= config.min_success_ratio
+  }
+
+  pub fn count(&mut self, error: &Error) {
+    *self.errors.entry(error.kind()).or_insert(0) += 1;
+    if let Error::Blocked { reason, .. } = error {
+      self.blocked = Some(reason.to_owned());
+    }
+  }
+
+  /// Records a search result that could not be transformed into a property.
+  pub fn add_failure(&mut self, error: &Error) {
+    self.count(error);
+    if let Error::Skipped(_) = error {
+      return;
+    }
+    *self.failures.entry(error.to_string()).or_insert(0) += 1;
+  }
+
+  /// Records the error that stopped the watcher.
+  pub fn fail(&mut self, error: Error) {
+    self.count(&error);
+    self.error = Some(error);
+  }
+
+  pub fn describe(&self) -> String {
+    let mut description = match self.error {
+      Some(ref error) => format!("failed: {}", error),
+      None => format!(
+        "{} of {} results on {} page(s) could be parsed",
+        self.parsed, self.results, self.pages
+      ),
+    };
+    if !self.errors.is_empty() {
+      let mut errors: Vec<(&&str, &usize)> = self.errors.iter().collect();
+      errors.sort();
+      let errors: Vec<String> = errors
+        .into_iter()
+        .map(|(kind, count)| format!("{} {}", count, kind))
+        .collect();
+      description.push_str(&format!(" (errors: {})", errors.join(", ")));
+    }
+    description
+  }
+}
+
+/// Keeps track of unhealthy watchers across runs.
+pub struct HealthMonitor {
+  unhealthy_runs: HashMap<String, u32>,
+}
+
+impl HealthMonitor {
+  pub fn new() -> Self {
+    HealthMonitor {
+      unhealthy_runs: HashMap::new(),
+    }
+  }
+
+  /// Records the health of a watcher's run and returns an alert message,
+  /// once the watcher has been unhealthy for the configured number of consecutive runs.
+  pub fn record(
+    &mut self,
+    config: &HealthConfig,
+    watcher: &Config,
+    health: &Health,
+  ) -> Option<String> {
+    // blocked portals are handled by the circuit breaker
+    if health.blocked.is_some() {
+      return None;
+    }
+    let key = format!("{}|{}", watcher.crawler, watcher.address);
+    if health.is_healthy(config) {
+      self.unhealthy_runs.remove(&key);
+      return None;
+    }
+
+    let unhealthy_runs = self.unhealthy_runs.entry(key).or_insert(0);
+    *unhealthy_runs += 1;
+    // only alert once, until the watcher recovers
+    if *unhealthy_runs == config.consecutive_runs {
+      let mut message = format!(
+        "{} watcher {} looks broken: {} ({} runs in a row).",
+        watcher.crawler,
+        watcher.city,
+        health.describe(),
+        unhealthy_runs
+      );
+      let mut failures: Vec<(&String, &usize)> = health.failures.iter().collect();
+      failures.sort_by(|a, b| b.1.cmp(a.1));
+      for (reason, count) in failures {
+        message.push_str(&format!("\n{}x {}", count, reason));
+      }
+      Some(message)
+    } else {
+      None
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Health, HealthMonitor};
+  use crate::configuration::HealthConfig;
+  use crate::crawlers::{Config, Error};
+  use crate::models::{ContractType, PropertyType};
+
+  fn watcher() -> Config {
+    Config {
+      address: String::from(
+        "https://www.immobilienscout24.de/Suche/de/bayern/muenchen/wohnung-mieten",
+      ),
+      city: String::from("Munich"),
+      crawler: String::from("immoscout"),
+      property_type: PropertyType::Flat,
+      contract_type: ContractType::Rent,
+      max_pages: 1,
+      incremental: true,
+      encoding: None,
+      interval: None,
+      cron: None,
+      jitter: 0,
+      quiet_hours: None,
+    }
+  }
+
+  fn broken() -> Health {
+    let mut health = Health {
+      pages: 1,
+      results: 20,
+      parsed: 2,
+      ..Default::default()
+    };
+    health.add_failure(&Error::field_selector(".result-list-entry__map-link"));
+    health
+  }
+
+  #[test]
+  fn alert_after_consecutive_runs() {
+    // GIVEN
+    let config = HealthConfig {
+      min_success_ratio: 0.5,
+      consecutive_runs: 2,
+    };
+    let mut monitor = HealthMonitor::new();
+
+    // WHEN
+    let first = monitor.record(&config, &watcher(), &broken());
+    let second = monitor.record(&config, &watcher(), &broken());
+    let third = monitor.record(&config, &watcher(), &broken());
+
+    // THEN
+    assert!(first.is_none());
+    assert!(second
+      .unwrap()
+      .starts_with("immoscout watcher Munich looks broken"));
+    assert!(third.is_none());
+  }
+
+  #[test]
+  fn reset_after_recovery() {
+    // GIVEN
+    let config = HealthConfig {
+      min_success_ratio: 0.5,
+      consecutive_runs: 2,
+    };
+    let mut monitor = HealthMonitor::new();
+    let healthy = Health {
+      pages: 1,
+      results: 20,
+      parsed: 20,
+      ..Default::default()
+    };
+
+    // WHEN
+    let first = monitor.record(&config, &watcher(), &broken());
+    let second = monitor.record(&config, &watcher(), &healthy);
+    let third = monitor.record(&config, &watcher(), &broken());
+
+    // THEN
+    assert!(first.is_none());
+    assert!(second.is_none());
+    assert!(third.is_none());
+  }
+
+  #[test]
+  fn ignore_skipped_results() {
+    // GIVEN
+    let config = HealthConfig {
+      min_success_ratio: 0.5,
+      consecutive_runs: 1,
+    };
+    let mut health = Health {
+      pages: 1,
+      results: 10,
+      parsed: 4,
+      ..Default::default()
+    };
+    for _ in 0..6 {
+      health.add_failure(&Error::Skipped(String::from("limited")));
+    }
+
+    // THEN
+    assert!(health.is_healthy(&config));
+    assert!(health.failures.is_empty());
+    assert_eq!(health.errors.get("skipped"), Some(&6));
+  }
+}
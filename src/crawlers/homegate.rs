@@ -0,0 +1,105 @@
+extern crate kuchiki;
+extern crate std;
+
+use super::crawler::with_query_param;
+use super::{Context, Crawler, Error};
+use crate::crawlers::quantity::{self, Locale, Quantity};
+use crate::crawlers::Metadata;
+use crate::models::{Currency, PropertyData};
+use kuchiki::{ElementData, NodeDataRef, NodeRef};
+use url::Url;
+
+pub struct Homegate {}
+
+impl Crawler for Homegate {
+  fn metadata(&self) -> Metadata {
+    Metadata {
+      name: String::from("homegate"),
+      encoding: encoding_rs::UTF_8,
+    }
+  }
+
+  fn selector(&self) -> &str {
+    "div[data-test=result-list-item]"
+  }
+
+  fn next_page(&self, _document: &NodeRef, url: &Url, page: u32) -> Option<String> {
+    Some(with_query_param(url, "ep", &(page + 1).to_string()))
+  }
+
+  /// Prices are written like "CHF 2’450.–".
+  fn parse_quantity(text: &str) -> Quantity {
+    quantity::parse(text, Locale::Ch)
+  }
+
+  fn transform_result(
+    &self,
+    context: &Context,
+    result: NodeDataRef<ElementData>,
+  ) -> Result<PropertyData, Error> {
+    // eg. /mieten/4000812345 or /kaufen/4000812345
+    let link = Self::get_attr(&result, Some("a"), "href")?;
+    let externalid = link
+      .trim_end_matches('/')
+      .rsplit('/')
+      .next()
+      .unwrap_or_default()
+      .to_string();
+    let price = Self::get_text(&result, "[class*=HgListingCard_price]")?;
+    let facts = Self::get_texts(&result, "[class*=HgListingRoomsLivingSpace] span")?;
+    let squaremeters = facts
+      .iter()
+      .find(|fact| fact.contains("m²"))
+      .cloned()
+      .ok_or_else(|| Error::Incomplete(String::from("no living space")))?;
+    let rooms = facts
+      .iter()
+      .find(|fact| fact.contains("Zimmer") || fact.contains("rooms"))
+      .cloned()
+      .ok_or_else(|| Error::Incomplete(String::from("no rooms")))?;
+    let title = Self::get_text(&result, "[class*=HgListingCard_title]")?
+      .trim()
+      .to_string();
+    let address = Self::get_text(&result, "address")?.trim().to_string();
+
+    Ok(PropertyData {
+      cold_rent: None,
+      // swiss portals state the rent including service charges (Bruttomiete)
+      warm_rent: Some(Self::parse_number(price)?),
+      service_charges: None,
+      deposit: None,
+      purchase_price: None,
+      currency: Currency::Chf,
+      squaremeters: Self::parse_number(squaremeters)?,
+      plot_squaremeters: None,
+      address,
+      title,
+      rooms: Self::parse_number(rooms)?,
+      url: context.absolute_url(&link)?,
+      externalid,
+      property_type: context.config.property_type.clone(),
+      contract_type: context.config.contract_type.clone(),
+      tags: vec![],
+      location: None,
+      available_from: None,
+      available_until: None,
+      room: None,
+      details: None,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Homegate;
+  use crate::crawlers::fixtures::assert_snapshot;
+
+  #[test]
+  fn transform_recorded_results() {
+    assert_snapshot(
+      &Homegate {},
+      "homegate",
+      "https://www.homegate.ch/mieten/wohnung/ort-zuerich/trefferliste",
+    );
+  }
+}
@@ -0,0 +1,217 @@
+use crate::configuration::HttpConfig;
+use crate::crawlers::Error;
+use reqwest::header::{CONTENT_TYPE, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use url::Url;
+
+pub struct Response {
+  pub body: Vec<u8>,
+  pub content_type: Option<String>,
+}
+
+/// HTTP client that is shared by all watchers of a run.
+/// It retries failed requests and keeps a minimum delay between requests to the same domain.
+pub struct HttpClient {
+  client: reqwest::Client,
+  config: HttpConfig,
+  next_request: Mutex<HashMap<String, Instant>>,
+}
+
+impl HttpClient {
+  pub fn new(config: &HttpConfig) -> Result<Self, Error> {
+    let client = reqwest::Client::builder()
+      .user_agent(config.user_agent.as_str())
+      .timeout(Duration::from_secs(config.timeout))
+      .connect_timeout(Duration::from_secs(config.connect_timeout))
+      .build()?;
+    Ok(HttpClient {
+      client,
+      config: config.clone(),
+      next_request: Mutex::new(HashMap::new()),
+    })
+  }
+
+  pub async fn get(&self, url: &str) -> Result<Response, Error> {
+    let mut attempt = 0;
+    loop {
+      self.wait_for_domain(url).await;
+      let retry_delay = match self.client.get(url).send().await {
+        Ok(response) if response.status().is_success() => {
+          let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+          return Ok(Response {
+            body: response.bytes().await?.to_vec(),
+            content_type,
+          });
+        }
+        Ok(response) => {
+          let status = response.status();
+          let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_retry_after(value, chrono::Utc::now()));
+          match retry_delay(&self.config, retry_after, attempt) {
+            Some(delay) if attempt < self.config.retries && is_transient(status) => delay,
+            _ => {
+              return Err(Error::Status {
+                url: url.to_owned(),
+                status,
+              })
+            }
+          }
+        }
+        Err(e) if attempt < self.config.retries && (e.is_timeout() || e.is_connect()) => {
+          backoff(&self.config, attempt).min(Duration::from_secs(self.config.max_retry_delay))
+        }
+        Err(e) => return Err(Error::Network(e)),
+      };
+      attempt += 1;
+      println!(
+        "request to '{}' failed, will retry in {} ms ({}/{}).",
+        url,
+        retry_delay.as_millis(),
+        attempt,
+        self.config.retries
+      );
+      tokio::time::delay_for(retry_delay).await;
+    }
+  }
+
+  /// Waits until the minimum delay since the last request to the url's domain has passed.
+  async fn wait_for_domain(&self, url: &str) {
+    let domain = match Url::parse(url)
+      .ok()
+      .and_then(|url| url.host_str().map(String::from))
+    {
+      Some(domain) => domain,
+      None => return,
+    };
+    let now = Instant::now();
+    let scheduled = {
+      let mut next_request = self.next_request.lock().await;
+      let scheduled = match next_request.get(&domain) {
+        Some(next) if *next > now => *next,
+        _ => now,
+      };
+      next_request.insert(
+        domain,
+        scheduled + Duration::from_millis(self.config.domain_delay),
+      );
+      scheduled
+    };
+    if scheduled > now {
+      tokio::time::delay_until(scheduled.into()).await;
+    }
+  }
+}
+
+fn is_transient(status: StatusCode) -> bool {
+  status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Returns how long to wait before retrying, or `None` if the portal asked to wait
+/// longer than the configured maximum.
+fn retry_delay(
+  config: &HttpConfig,
+  retry_after: Option<Duration>,
+  attempt: u32,
+) -> Option<Duration> {
+  let max_retry_delay = Duration::from_secs(config.max_retry_delay);
+  match retry_after {
+    Some(delay) if delay > max_retry_delay => None,
+    Some(delay) => Some(delay),
+    None => Some(backoff(config, attempt).min(max_retry_delay)),
+  }
+}
+
+/// Exponential backoff: the configured delay is doubled with every attempt.
+fn backoff(config: &HttpConfig, attempt: u32) -> Duration {
+  Duration::from_millis(config.backoff.saturating_mul(2u64.saturating_pow(attempt)))
+}
+
+/// Parses the value of a `Retry-After` header, which is either given in seconds or as a date.
+fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+  match value.trim().parse::<u64>() {
+    Ok(seconds) => Some(Duration::from_secs(seconds)),
+    Err(_) => chrono::DateTime::parse_from_rfc2822(value.trim())
+      .ok()
+      .map(|date| {
+        (date.with_timezone(&chrono::Utc) - now)
+          .to_std()
+          .unwrap_or_else(|_| Duration::from_secs(0))
+      }),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{backoff, parse_retry_after, retry_delay};
+  use crate::configuration::HttpConfig;
+  use chrono::TimeZone;
+  use std::time::Duration;
+
+  #[test]
+  fn retry_after_in_seconds() {
+    assert_eq!(
+      parse_retry_after("120", chrono::Utc::now()),
+      Some(Duration::from_secs(120))
+    );
+  }
+
+  #[test]
+  fn retry_after_as_date() {
+    // GIVEN
+    let now = chrono::Utc.ymd(2020, 8, 1).and_hms(12, 0, 0);
+
+    // WHEN
+    let delay = parse_retry_after("Sat, 01 Aug 2020 12:00:30 GMT", now);
+
+    // THEN
+    assert_eq!(delay, Some(Duration::from_secs(30)));
+  }
+
+  fn config() -> HttpConfig {
+    HttpConfig {
+      user_agent: String::from("properwatcher"),
+      timeout: 30,
+      connect_timeout: 10,
+      retries: 3,
+      backoff: 500,
+      max_retry_delay: 60,
+      domain_delay: 1000,
+    }
+  }
+
+  #[test]
+  fn backoff_doubles() {
+    assert_eq!(backoff(&config(), 0), Duration::from_millis(500));
+    assert_eq!(backoff(&config(), 2), Duration::from_millis(2000));
+  }
+
+  #[test]
+  fn retry_delay_limited() {
+    // GIVEN
+    let config = config();
+
+    // THEN
+    assert_eq!(
+      retry_delay(&config, Some(Duration::from_secs(30)), 0),
+      Some(Duration::from_secs(30))
+    );
+    assert_eq!(
+      retry_delay(&config, Some(Duration::from_secs(86400)), 0),
+      None
+    );
+    assert_eq!(
+      retry_delay(&config, None, 20),
+      Some(Duration::from_secs(60))
+    );
+  }
+}
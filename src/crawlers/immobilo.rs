@@ -0,0 +1,175 @@
+extern crate kuchiki;
+extern crate std;
+
+use super::crawler::{assign_price, with_query_param};
+use super::{Context, Crawler, Error};
+use crate::crawlers::Metadata;
+use crate::models::{Currency, PropertyData};
+use kuchiki::{ElementData, NodeDataRef, NodeRef};
+use url::Url;
+
+pub struct Immobilo {}
+
+impl Crawler for Immobilo {
+  fn metadata(&self) -> Metadata {
+    Metadata {
+      name: String::from("immobilo"),
+      encoding: encoding_rs::UTF_8,
+    }
+  }
+
+  fn selector(&self) -> &str {
+    "article.result-item[data-id]"
+  }
+
+  fn next_page(&self, document: &NodeRef, url: &Url, page: u32) -> Option<String> {
+    document.select_first("a[rel=next]").ok()?;
+    Some(with_query_param(url, "page", &(page + 1).to_string()))
+  }
+
+  fn transform_result(
+    &self,
+    context: &Context,
+    result: NodeDataRef<ElementData>,
+  ) -> Result<PropertyData, Error> {
+    let externalid = Self::get_attr(&result, None, "data-id")?;
+    let price = Self::get_text(&result, ".result-item__price strong")?;
+    // eg. "Kaltmiete", "Warmmiete" or "Kaufpreis"
+    let price_label =
+      Self::get_optional_text(&result, ".result-item__price span").unwrap_or_default();
+    let squaremeters = Self::get_text(&result, ".result-item__area strong")?;
+    let rooms = Self::get_text(&result, ".result-item__rooms strong")?;
+    // houses state the size of their plot next to the living space
+    let plot_squaremeters = Self::get_optional_text(&result, ".result-item__plot strong")
+      .and_then(|plot| Self::parse_number(plot).ok());
+    let title = Self::get_text(&result, ".result-item__title")?
+      .trim()
+      .to_string();
+    let link = Self::get_attr(&result, Some("a.result-item__link"), "href")?;
+    let address = Self::get_text(&result, ".result-item__address")?
+      .trim()
+      .to_string();
+    let tags = Self::get_texts(&result, ".result-item__tags li")?;
+    let mut data = PropertyData {
+      cold_rent: None,
+      warm_rent: None,
+      service_charges: None,
+      deposit: None,
+      purchase_price: None,
+      currency: Currency::Eur,
+      squaremeters: Self::parse_number(squaremeters)?,
+      plot_squaremeters,
+      address,
+      title,
+      rooms: Self::parse_number(rooms)?,
+      url: context.absolute_url(&link)?,
+      externalid,
+      property_type: context.config.property_type.clone(),
+      contract_type: context.config.contract_type.clone(),
+      tags,
+      location: None,
+      available_from: None,
+      available_until: None,
+      room: None,
+      details: None,
+    };
+    assign_price(&mut data, &price_label, Self::parse_number(price)?);
+    Ok(data)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Immobilo;
+  use crate::crawlers::fixtures::assert_snapshot;
+  use crate::crawlers::{Config, Context, Crawler};
+  use crate::models::{ContractType, PropertyType};
+  use chrono::Local;
+  use kuchiki::traits::*;
+  use url::Url;
+
+  #[test]
+  fn transform_recorded_results() {
+    assert_snapshot(
+      &Immobilo {},
+      "immobilo",
+      "https://www.immobilo.de/suche/wohnungen/mieten/muenchen",
+    );
+  }
+
+  #[test]
+  fn transform_house_for_sale() {
+    // GIVEN
+    let config = Config {
+      address: String::from("https://www.immobilo.de/suche/haeuser/kaufen/muenchen"),
+      city: String::from("München"),
+      crawler: String::from("immobilo"),
+      property_type: PropertyType::House,
+      contract_type: ContractType::Buy,
+      max_pages: 1,
+      incremental: true,
+      encoding: None,
+      interval: None,
+      cron: None,
+      jitter: 0,
+      quiet_hours: None,
+    };
+    let url = Url::parse(&config.address).unwrap();
+    let context = Context {
+      config: &config,
+      url: &url,
+      page: 1,
+      fetched: Local::now(),
+    };
+    let document = kuchiki::parse_html().one(
+      r#"<article class="result-item" data-id="90211873">
+        <a class="result-item__link" href="/expose/90211873">
+          <h3 class="result-item__title">Reihenmittelhaus mit Garten</h3>
+        </a>
+        <p class="result-item__address">81247 München, Obermenzing</p>
+        <ul class="result-item__facts">
+          <li class="result-item__price"><strong>985.000 €</strong><span>Kaufpreis</span></li>
+          <li class="result-item__area"><strong>126 m²</strong><span>Wohnfläche</span></li>
+          <li class="result-item__rooms"><strong>5</strong><span>Zimmer</span></li>
+          <li class="result-item__plot"><strong>210 m²</strong><span>Grundstück</span></li>
+        </ul>
+      </article>"#,
+    );
+    let crawler = Immobilo {};
+    let result = document.select_first(crawler.selector()).unwrap();
+
+    // WHEN
+    let data = crawler.transform_result(&context, result).ok().unwrap();
+
+    // THEN
+    assert_eq!(data.purchase_price, Some(985_000.0));
+    assert_eq!(data.cold_rent, None);
+    assert_eq!(data.squaremeters, 126.0);
+    assert_eq!(data.plot_squaremeters, Some(210.0));
+    assert_eq!(data.address, "81247 München, Obermenzing");
+    assert_eq!(data.url, "https://www.immobilo.de/expose/90211873");
+  }
+
+  #[test]
+  fn stop_after_last_page() {
+    // GIVEN
+    let url = Url::parse("https://www.immobilo.de/suche/wohnungen/mieten/muenchen?page=2").unwrap();
+    let last_page = kuchiki::parse_html()
+      .one(r#"<nav class="pagination"><a rel="prev" href="?page=1">1</a></nav>"#);
+    let further_pages = kuchiki::parse_html()
+      .one(r#"<nav class="pagination"><a rel="next" href="?page=3">3</a></nav>"#);
+
+    // WHEN
+    let after_last_page = Immobilo {}.next_page(&last_page, &url, 2);
+    let after_further_pages = Immobilo {}.next_page(&further_pages, &url, 2);
+
+    // THEN
+    assert_eq!(after_last_page, None);
+    assert_eq!(
+      after_further_pages,
+      Some(String::from(
+        "https://www.immobilo.de/suche/wohnungen/mieten/muenchen?page=3"
+      ))
+    );
+  }
+}
@@ -0,0 +1,153 @@
+extern crate kuchiki;
+extern crate std;
+
+use super::crawler::{assign_price, with_query_param};
+use super::{Context, Crawler, Error};
+use crate::crawlers::Metadata;
+use crate::models::{Currency, PropertyData};
+use kuchiki::{ElementData, NodeDataRef, NodeRef};
+use url::Url;
+
+pub struct Immonet {}
+
+impl Crawler for Immonet {
+  fn metadata(&self) -> Metadata {
+    Metadata {
+      name: String::from("immonet"),
+      encoding: encoding_rs::UTF_8,
+    }
+  }
+
+  fn selector(&self) -> &str {
+    "div[id^=selObject_]"
+  }
+
+  fn next_page(&self, _document: &NodeRef, url: &Url, page: u32) -> Option<String> {
+    Some(with_query_param(url, "page", &(page + 1).to_string()))
+  }
+
+  fn transform_result(
+    &self,
+    context: &Context,
+    result: NodeDataRef<ElementData>,
+  ) -> Result<PropertyData, Error> {
+    let externalid = Self::get_attr(&result, None, "id")?
+      .trim_start_matches("selObject_")
+      .to_string();
+    let price = Self::get_text(&result, "[id^=selPrice_] span")?;
+    // eg. "Miete zzgl. NK", "Warmmiete" or "Kaufpreis"
+    let price_label = Self::get_optional_text(&result, "[id^=selPrice_] p").unwrap_or_default();
+    let squaremeters = Self::get_text(&result, "[id^=selArea_] span")?;
+    let rooms = Self::get_text(&result, "[id^=selRooms_] span")?;
+    // houses state the size of their plot next to the living space
+    let plot_squaremeters = Self::get_optional_text(&result, "[id^=selPlotArea_] span")
+      .and_then(|plot| Self::parse_number(plot).ok());
+    let title = Self::get_text(&result, "a[id^=lnkToDetails_]")?
+      .trim()
+      .to_string();
+    let link = Self::get_attr(&result, Some("a[id^=lnkToDetails_]"), "href")?;
+    // the location follows the kind of property, eg. "Etagenwohnung • München / Neuhausen"
+    let address = Self::get_text(&result, ".ellipsis.text-100")?
+      .rsplit('•')
+      .next()
+      .unwrap_or_default()
+      .trim()
+      .to_string();
+    let mut data = PropertyData {
+      cold_rent: None,
+      warm_rent: None,
+      service_charges: None,
+      deposit: None,
+      purchase_price: None,
+      currency: Currency::Eur,
+      squaremeters: Self::parse_number(squaremeters)?,
+      plot_squaremeters,
+      address,
+      title,
+      rooms: Self::parse_number(rooms)?,
+      url: context.absolute_url(&link)?,
+      externalid,
+      property_type: context.config.property_type.clone(),
+      contract_type: context.config.contract_type.clone(),
+      tags: vec![],
+      location: None,
+      available_from: None,
+      available_until: None,
+      room: None,
+      details: None,
+    };
+    assign_price(&mut data, &price_label, Self::parse_number(price)?);
+    Ok(data)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Immonet;
+  use crate::crawlers::fixtures::assert_snapshot;
+  use crate::crawlers::{Config, Context, Crawler};
+  use crate::models::{ContractType, PropertyType};
+  use chrono::Local;
+  use kuchiki::traits::*;
+  use url::Url;
+
+  #[test]
+  fn transform_recorded_results() {
+    assert_snapshot(
+      &Immonet {},
+      "immonet",
+      "https://www.immonet.de/immobiliensuche/sel.do?city=121673&marketingtype=2&objecttype=1",
+    );
+  }
+
+  #[test]
+  fn transform_house_for_sale() {
+    // GIVEN
+    let config = Config {
+      address: String::from(
+        "https://www.immonet.de/immobiliensuche/sel.do?city=121673&marketingtype=1&objecttype=2",
+      ),
+      city: String::from("München"),
+      crawler: String::from("immonet"),
+      property_type: PropertyType::House,
+      contract_type: ContractType::Buy,
+      max_pages: 1,
+      incremental: true,
+      encoding: None,
+      interval: None,
+      cron: None,
+      jitter: 0,
+      quiet_hours: None,
+    };
+    let url = Url::parse(&config.address).unwrap();
+    let context = Context {
+      config: &config,
+      url: &url,
+      page: 1,
+      fetched: Local::now(),
+    };
+    let document = kuchiki::parse_html().one(
+      r#"<div id="selObject_41377001">
+        <a href="/angebot/41377001" id="lnkToDetails_41377001">Doppelhaushälfte in Pasing</a>
+        <div class="text-100 ellipsis"><span>Doppelhaushälfte &bull; </span>München / Pasing</div>
+        <div id="selPrice_41377001"><span>1.249.000 €</span><p>Kaufpreis</p></div>
+        <div id="selArea_41377001"><span>142 m²</span><p>Wohnfläche</p></div>
+        <div id="selRooms_41377001"><span>5</span><p>Zimmer</p></div>
+        <div id="selPlotArea_41377001"><span>318 m²</span><p>Grundstück</p></div>
+      </div>"#,
+    );
+    let crawler = Immonet {};
+    let result = document.select_first(crawler.selector()).unwrap();
+
+    // WHEN
+    let data = crawler.transform_result(&context, result).ok().unwrap();
+
+    // THEN
+    assert_eq!(data.purchase_price, Some(1_249_000.0));
+    assert_eq!(data.cold_rent, None);
+    assert_eq!(data.squaremeters, 142.0);
+    assert_eq!(data.plot_squaremeters, Some(318.0));
+    assert_eq!(data.address, "München / Pasing");
+    assert_eq!(data.url, "https://www.immonet.de/angebot/41377001");
+  }
+}
@@ -2,11 +2,12 @@ extern crate kuchiki;
 extern crate reqwest;
 extern crate std;
 
-use super::{Crawler, Error};
+use super::crawler::{assign_price, with_query_param};
+use super::{Context, Crawler, Error};
 use crate::crawlers::Metadata;
-use crate::models::Encoding;
-use crate::models::{ContractType, PropertyData, PropertyType};
-use kuchiki::{ElementData, NodeDataRef};
+use crate::models::{Currency, PropertyData, PropertyDetails};
+use kuchiki::{ElementData, NodeDataRef, NodeRef};
+use url::Url;
 
 pub struct ImmoScout {}
 
@@ -14,16 +15,63 @@ impl Crawler for ImmoScout {
   fn metadata(&self) -> Metadata {
     Metadata {
       name: String::from("immoscout"),
-      encoding: Encoding::Utf8,
+      encoding: encoding_rs::UTF_8,
     }
   }
 
-  fn selector(&self) -> &'static str {
+  fn selector(&self) -> &str {
     "article[data-item=result]"
   }
 
-  fn transform_result(&self, result: NodeDataRef<ElementData>) -> Result<PropertyData, Error> {
+  fn has_expose(&self) -> bool {
+    true
+  }
+
+  fn transform_expose(
+    &self,
+    expose: NodeDataRef<ElementData>,
+    data: &mut PropertyData,
+  ) -> Result<(), Error> {
+    // deposits are often given relative to the rent, eg. "3 Nettokaltmieten"
+    data.deposit = Self::get_optional_text(&expose, ".is24qa-kaution-o-genossenschaftsanteile")
+      .filter(|deposit| deposit.contains('€'))
+      .and_then(|deposit| Self::parse_number(deposit).ok())
+      .or(data.deposit);
+    data.service_charges = Self::get_optional_text(&expose, ".is24qa-nebenkosten")
+      .and_then(|charges| Self::parse_number(charges).ok())
+      .or(data.service_charges);
+    data.warm_rent = Self::get_optional_text(&expose, ".is24qa-gesamtmiete")
+      .and_then(|rent| Self::parse_number(rent).ok())
+      .or(data.warm_rent);
+    data.available_from = Self::get_optional_text(&expose, ".is24qa-bezugsfrei-ab")
+      .and_then(|date| Self::parse_date(&date))
+      .or(data.available_from);
+    data.details = Some(PropertyDetails {
+      floor: Self::get_optional_text(&expose, ".is24qa-etage"),
+      year_of_construction: Self::get_optional_text(&expose, ".is24qa-baujahr")
+        .and_then(|year| Self::parse_number(year).ok())
+        .map(|year| year as u32),
+      energy_certificate: Self::get_optional_text(&expose, ".is24qa-energieausweis"),
+      heating_type: Self::get_optional_text(&expose, ".is24qa-heizungsart"),
+      description: Self::get_optional_text(&expose, ".is24qa-objektbeschreibung"),
+    });
+    Ok(())
+  }
+
+  fn next_page(&self, _document: &NodeRef, url: &Url, page: u32) -> Option<String> {
+    Some(with_query_param(url, "pagenumber", &(page + 1).to_string()))
+  }
+
+  fn transform_result(
+    &self,
+    context: &Context,
+    result: NodeDataRef<ElementData>,
+  ) -> Result<PropertyData, Error> {
     let rent = Self::get_text(&result, ".result-list-entry__criteria dl:nth-child(1) dd")?;
+    // eg. "Kaltmiete", "Warmmiete" or "Kaufpreis"
+    let rent_label =
+      Self::get_optional_text(&result, ".result-list-entry__criteria dl:nth-child(1) dt")
+        .unwrap_or_default();
     let squaremeters = Self::get_text(&result, ".result-list-entry__criteria dl:nth-child(2) dd")?;
     let rooms = Self::get_text(
       &result,
@@ -43,8 +91,13 @@ impl Crawler for ImmoScout {
       .into_iter()
       .filter(|tag| tag != "...")
       .collect();
-    Ok(PropertyData {
-      price: Self::parse_number(rent)?,
+    let mut data = PropertyData {
+      cold_rent: None,
+      warm_rent: None,
+      service_charges: None,
+      deposit: None,
+      purchase_price: None,
+      currency: Currency::Eur,
       squaremeters: Self::parse_number(squaremeters)?,
       address,
       title,
@@ -52,9 +105,36 @@ impl Crawler for ImmoScout {
       rooms: Self::parse_number(rooms)?,
       url: format!("http://www.immobilienscout24.de/expose/{}", &externalid),
       externalid,
-      property_type: PropertyType::Flat,
-      contract_type: ContractType::Rent,
+      property_type: context.config.property_type.clone(),
+      contract_type: context.config.contract_type.clone(),
       tags,
-    })
+      location: None,
+      available_from: None,
+      available_until: None,
+      room: None,
+      details: None,
+    };
+    assign_price(&mut data, &rent_label, Self::parse_number(rent)?);
+    Ok(data)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ImmoScout;
+  use crate::crawlers::fixtures::{assert_expose_snapshot, assert_snapshot};
+
+  #[test]
+  fn transform_recorded_results() {
+    assert_snapshot(
+      &ImmoScout {},
+      "immoscout",
+      "https://www.immobilienscout24.de/Suche/de/bayern/muenchen/wohnung-mieten",
+    );
+  }
+
+  #[test]
+  fn transform_recorded_expose() {
+    assert_expose_snapshot(&ImmoScout {}, "immoscout");
   }
 }
@@ -3,11 +3,12 @@ extern crate regex;
 extern crate reqwest;
 extern crate std;
 
-use super::{Crawler, Error};
+use super::crawler::{assign_price, with_query_param};
+use super::{Context, Crawler, Error};
 use crate::crawlers::Metadata;
-use crate::models::Encoding;
-use crate::models::{ContractType, PropertyData, PropertyType};
-use kuchiki::{ElementData, NodeDataRef};
+use crate::models::{Currency, PropertyData, PropertyDetails};
+use kuchiki::{ElementData, NodeDataRef, NodeRef};
+use url::Url;
 
 pub struct ImmoWelt {
   pub brackets: regex::Regex,
@@ -19,24 +20,87 @@ impl ImmoWelt {
       brackets: regex::Regex::new(r"\s*\([^)]*\)").unwrap(),
     };
   }
+
+  /// Looks up a fact on the expose by its label, eg. "Kaution".
+  fn get_fact(expose: &NodeDataRef<ElementData>, label: &str) -> Option<String> {
+    expose
+      .as_node()
+      .select(".datarow")
+      .ok()?
+      .find(|row| {
+        Self::get_text(row, ".datalabel")
+          .map(|text| text.trim().starts_with(label))
+          .unwrap_or(false)
+      })
+      .and_then(|row| Self::get_optional_text(&row, ".datacontent"))
+  }
 }
 
 impl Crawler for ImmoWelt {
   fn metadata(&self) -> Metadata {
     Metadata {
       name: String::from("immowelt"),
-      encoding: Encoding::Utf8,
+      encoding: encoding_rs::UTF_8,
     }
   }
 
-  fn selector(&self) -> &'static str {
+  fn selector(&self) -> &str {
     ".js-object[data-estateid]"
   }
 
-  fn transform_result(&self, result: NodeDataRef<ElementData>) -> Result<PropertyData, Error> {
+  fn has_expose(&self) -> bool {
+    true
+  }
+
+  fn transform_expose(
+    &self,
+    expose: NodeDataRef<ElementData>,
+    data: &mut PropertyData,
+  ) -> Result<(), Error> {
+    // deposits are often given relative to the rent, eg. "3 Monatsmieten"
+    data.deposit = Self::get_fact(&expose, "Kaution")
+      .filter(|deposit| deposit.contains('€'))
+      .and_then(|deposit| Self::parse_number(deposit).ok())
+      .or(data.deposit);
+    data.service_charges = Self::get_fact(&expose, "Nebenkosten")
+      .and_then(|charges| Self::parse_number(charges).ok())
+      .or(data.service_charges);
+    data.warm_rent = Self::get_fact(&expose, "Warmmiete")
+      .and_then(|rent| Self::parse_number(rent).ok())
+      .or(data.warm_rent);
+    data.available_from = Self::get_fact(&expose, "Bezug")
+      .and_then(|date| Self::parse_date(&date))
+      .or(data.available_from);
+    data.details = Some(PropertyDetails {
+      floor: Self::get_fact(&expose, "Etage"),
+      year_of_construction: Self::get_fact(&expose, "Baujahr")
+        .and_then(|year| Self::parse_number(year).ok())
+        .map(|year| year as u32),
+      energy_certificate: Self::get_fact(&expose, "Energieausweis"),
+      heating_type: Self::get_fact(&expose, "Heizungsart"),
+      description: Self::get_optional_text(&expose, "#divImmobilie .section_content p"),
+    });
+    Ok(())
+  }
+
+  fn next_page(&self, _document: &NodeRef, url: &Url, page: u32) -> Option<String> {
+    Some(with_query_param(url, "cp", &(page + 1).to_string()))
+  }
+
+  fn transform_result(
+    &self,
+    context: &Context,
+    result: NodeDataRef<ElementData>,
+  ) -> Result<PropertyData, Error> {
     let rent = Self::get_text(&result, ".hardfacts_3 .hardfact:nth-child(1) strong")?;
     let squaremeters = Self::get_text(&result, ".hardfacts_3 .hardfact:nth-child(2)")?;
     let rooms = Self::get_text(&result, ".hardfacts_3 .hardfact:nth-child(3)")?;
+    // eg. "Kaltmiete", "Warmmiete" or "Kaufpreis"
+    let rent_label = Self::get_optional_text(
+      &result,
+      ".hardfacts_3 .hardfact:nth-child(1) .hardfactlabel",
+    )
+    .unwrap_or_default();
     let title = Self::get_text(&result, ".listcontent h2")?;
     let address = Self::get_text(&result, ".listlocation")?
       .split("\n")
@@ -46,8 +110,13 @@ impl Crawler for ImmoWelt {
       .join(", ");
     let cleaned_address = self.brackets.replace_all(&address, "").into_owned();
     let externalid = Self::get_attr(&result, None, "data-estateid")?;
-    Ok(PropertyData {
-      price: Self::parse_number(rent)?,
+    let mut data = PropertyData {
+      cold_rent: None,
+      warm_rent: None,
+      service_charges: None,
+      deposit: None,
+      purchase_price: None,
+      currency: Currency::Eur,
       squaremeters: Self::parse_number(squaremeters)?,
       plot_squaremeters: None,
       address: cleaned_address,
@@ -55,9 +124,36 @@ impl Crawler for ImmoWelt {
       rooms: Self::parse_number(rooms)?,
       url: format!("https://www.immowelt.de/expose/{}", &externalid),
       externalid,
-      property_type: PropertyType::Flat,
-      contract_type: ContractType::Rent,
+      property_type: context.config.property_type.clone(),
+      contract_type: context.config.contract_type.clone(),
       tags: vec![],
-    })
+      location: None,
+      available_from: None,
+      available_until: None,
+      room: None,
+      details: None,
+    };
+    assign_price(&mut data, &rent_label, Self::parse_number(rent)?);
+    Ok(data)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ImmoWelt;
+  use crate::crawlers::fixtures::{assert_expose_snapshot, assert_snapshot};
+
+  #[test]
+  fn transform_recorded_results() {
+    assert_snapshot(
+      &ImmoWelt::new(),
+      "immowelt",
+      "https://www.immowelt.de/liste/muenchen/wohnungen/mieten",
+    );
+  }
+
+  #[test]
+  fn transform_recorded_expose() {
+    assert_expose_snapshot(&ImmoWelt::new(), "immowelt");
   }
 }
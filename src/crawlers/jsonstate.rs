@@ -0,0 +1,292 @@
+extern crate kuchiki;
+extern crate regex;
+extern crate serde_json;
+extern crate std;
+
+use super::{Context, Crawler, Error};
+use crate::crawlers::config::{JsonFieldsConfig, JsonStateConfig};
+use crate::crawlers::quantity::{self, Locale};
+use crate::crawlers::{charset, Metadata};
+use crate::models::{Currency, PropertyData};
+use kuchiki::{ElementData, NodeDataRef, NodeRef};
+use regex::Regex;
+use serde_json::Value;
+
+/// Crawler for portals that render their result list from a JSON state embedded into the page,
+/// which is completely defined by a `[[json_crawler]]` section of the configuration.
+pub struct JsonStateCrawler {
+  name: String,
+  selector: String,
+  regex: Option<Regex>,
+  results: Option<String>,
+  encoding: &'static encoding_rs::Encoding,
+  locale: Locale,
+  currency: Currency,
+  url: Option<String>,
+  fields: JsonFieldsConfig,
+}
+
+impl JsonStateCrawler {
+  pub fn new(config: &JsonStateConfig) -> Result<Self, String> {
+    if config.url.is_none() && config.fields.url.is_none() {
+      return Err(String::from("either url or fields.url has to be given"));
+    }
+    Ok(JsonStateCrawler {
+      name: config.name.clone(),
+      selector: config.script.selector.clone(),
+      regex: match config.script.regex {
+        Some(ref pattern) => {
+          Some(Regex::new(pattern).map_err(|e| format!("invalid expression '{}': {}", pattern, e))?)
+        }
+        None => None,
+      },
+      results: config.results.clone(),
+      encoding: charset::for_label(&config.encoding)
+        .ok_or_else(|| format!("unknown encoding '{}'", config.encoding))?,
+      locale: config
+        .locale
+        .parse()
+        .map_err(|_| format!("unknown locale '{}'", config.locale))?,
+      currency: config.currency,
+      url: config.url.clone(),
+      fields: config.fields.clone(),
+    })
+  }
+
+  /// Parses the JSON within a script element.
+  fn parse_script(&self, script: &NodeDataRef<ElementData>) -> Result<Value, Error> {
+    let text = script.text_contents();
+    let json = match self.regex {
+      Some(ref regex) => regex
+        .captures(&text)
+        .and_then(|captures| captures.get(1).or_else(|| captures.get(0)))
+        .map(|capture| capture.as_str())
+        .ok_or_else(|| Error::Incomplete(format!("script does not match '{}'!", regex.as_str())))?,
+      None => text.trim(),
+    };
+    serde_json::from_str(json).map_err(Error::Json)
+  }
+
+  fn get_value(listing: &Value, path: &str) -> Result<String, Error> {
+    match lookup(listing, path) {
+      Some(Value::String(text)) => Ok(text.trim().to_owned()),
+      Some(Value::Number(number)) => Ok(number.to_string()),
+      _ => Err(Error::field_selector(path)),
+    }
+  }
+
+  fn get_values(listing: &Value, path: &str) -> Vec<String> {
+    match lookup(listing, path) {
+      Some(Value::Array(values)) => values
+        .iter()
+        .filter_map(|value| match value {
+          Value::String(text) => Some(text.trim().to_owned()),
+          Value::Number(number) => Some(number.to_string()),
+          _ => None,
+        })
+        .filter(|text| !text.is_empty())
+        .collect(),
+      _ => Self::get_value(listing, path).into_iter().collect(),
+    }
+  }
+
+  fn get_number(&self, listing: &Value, path: &str) -> Result<f32, Error> {
+    match lookup(listing, path) {
+      Some(Value::Number(number)) => {
+        number
+          .as_f64()
+          .map(|number| number as f32)
+          .ok_or_else(|| Error::Number {
+            value: number.to_string(),
+          })
+      }
+      Some(Value::String(text)) => match quantity::parse(text, self.locale).value() {
+        Some(number) => Ok(number),
+        None => Err(Error::Number {
+          value: text.to_owned(),
+        }),
+      },
+      _ => Err(Error::field_selector(path)),
+    }
+  }
+
+  fn get_optional_number(&self, listing: &Value, path: &Option<String>) -> Option<f32> {
+    path
+      .as_ref()
+      .and_then(|path| self.get_number(listing, path).ok())
+  }
+
+  fn transform_listing(&self, context: &Context, listing: &Value) -> Result<PropertyData, Error> {
+    let fields = &self.fields;
+    let externalid = Self::get_value(listing, &fields.externalid)?;
+    let url = match (&fields.url, &self.url) {
+      (Some(path), _) => Self::get_value(listing, path)?,
+      (None, Some(template)) => template.replace("{externalid}", &externalid),
+      (None, None) => return Err(Error::Incomplete(String::from("no url"))),
+    };
+    Ok(PropertyData {
+      cold_rent: Some(self.get_number(listing, &fields.price)?),
+      warm_rent: self.get_optional_number(listing, &fields.warm_rent),
+      service_charges: self.get_optional_number(listing, &fields.service_charges),
+      deposit: self.get_optional_number(listing, &fields.deposit),
+      purchase_price: None,
+      currency: self.currency,
+      squaremeters: self.get_number(listing, &fields.squaremeters)?,
+      plot_squaremeters: self.get_optional_number(listing, &fields.plot_squaremeters),
+      address: Self::get_value(listing, &fields.address)?,
+      title: Self::get_value(listing, &fields.title)?,
+      rooms: self.get_number(listing, &fields.rooms)?,
+      url: context.absolute_url(&url)?,
+      externalid,
+      property_type: context.config.property_type.clone(),
+      contract_type: context.config.contract_type.clone(),
+      tags: fields
+        .tags
+        .as_ref()
+        .map(|path| Self::get_values(listing, path))
+        .unwrap_or_default(),
+      location: None,
+      available_from: None,
+      available_until: None,
+      room: None,
+      details: None,
+    })
+  }
+}
+
+/// Looks up the value at a path like `resultList.entries[0].price` or `resultList.entries.0.price`.
+fn lookup<'v>(value: &'v Value, path: &str) -> Option<&'v Value> {
+  path
+    .replace('[', ".")
+    .replace(']', "")
+    .split('.')
+    .filter(|key| !key.is_empty())
+    .try_fold(value, |value, key| match value {
+      Value::Array(items) => key.parse::<usize>().ok().and_then(|index| items.get(index)),
+      Value::Object(entries) => entries.get(key),
+      _ => None,
+    })
+}
+
+impl Crawler for JsonStateCrawler {
+  fn metadata(&self) -> Metadata {
+    Metadata {
+      name: self.name.clone(),
+      encoding: self.encoding,
+    }
+  }
+
+  fn selector(&self) -> &str {
+    &self.selector
+  }
+
+  /// Transforms a script that contains the JSON of a single listing.
+  fn transform_result(
+    &self,
+    context: &Context,
+    result: NodeDataRef<ElementData>,
+  ) -> Result<PropertyData, Error> {
+    let listing = self.parse_script(&result)?;
+    self.transform_listing(context, &listing)
+  }
+
+  fn transform_document(
+    &self,
+    context: &Context,
+    document: &NodeRef,
+  ) -> Result<Vec<Result<PropertyData, Error>>, Error> {
+    let scripts = document
+      .select(&self.selector)
+      .map_err(|_| Error::MainSelector(self.selector.clone()))?;
+    let path = match self.results {
+      Some(ref path) => path,
+      None => {
+        return Ok(
+          scripts
+            .map(|script| self.transform_result(context, script))
+            .collect(),
+        )
+      }
+    };
+    // the page may contain several scripts matching the selector, the state is the one with listings
+    let state = scripts
+      .filter_map(|script| self.parse_script(&script).ok())
+      .find(|state| matches!(lookup(state, path), Some(Value::Array(_))));
+    match state.as_ref().and_then(|state| lookup(state, path)) {
+      Some(Value::Array(listings)) => Ok(
+        listings
+          .iter()
+          .map(|listing| self.transform_listing(context, listing))
+          .collect(),
+      ),
+      _ => Err(Error::MainSelector(format!("{} ({})", self.selector, path))),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{lookup, JsonStateCrawler};
+  use crate::crawlers::config::{JsonFieldsConfig, JsonStateConfig, ScriptConfig};
+  use crate::crawlers::fixtures::assert_snapshot;
+  use crate::models::Currency;
+
+  #[test]
+  fn lookup_nested_path() {
+    // GIVEN
+    let state = serde_json::json!({
+      "resultList": { "entries": [ { "price": { "value": 1250 } } ] }
+    });
+
+    // THEN
+    assert_eq!(
+      lookup(&state, "resultList.entries[0].price.value"),
+      Some(&serde_json::json!(1250))
+    );
+    assert_eq!(
+      lookup(&state, "resultList.entries.0.price.value"),
+      Some(&serde_json::json!(1250))
+    );
+    assert_eq!(lookup(&state, "resultList.entries[1].price"), None);
+  }
+
+  #[test]
+  fn transform_recorded_results() {
+    // GIVEN
+    let config = JsonStateConfig {
+      name: String::from("jsonstate"),
+      script: ScriptConfig {
+        selector: String::from("script"),
+        regex: Some(String::from(
+          r"(?s)window\.__INITIAL_STATE__\s*=\s*(\{.*\});",
+        )),
+      },
+      results: Some(String::from("searchResult.listings")),
+      encoding: String::from("utf-8"),
+      locale: String::from("de"),
+      currency: Currency::Eur,
+      url: Some(String::from("/expose/{externalid}")),
+      fields: JsonFieldsConfig {
+        externalid: String::from("id"),
+        title: String::from("title"),
+        address: String::from("location.address"),
+        price: String::from("prices.coldRent"),
+        squaremeters: String::from("livingSpace"),
+        rooms: String::from("rooms"),
+        url: None,
+        plot_squaremeters: None,
+        warm_rent: Some(String::from("prices.warmRent")),
+        service_charges: Some(String::from("prices.serviceCharges")),
+        deposit: Some(String::from("prices.deposit")),
+        tags: Some(String::from("features")),
+      },
+    };
+
+    // THEN
+    assert_snapshot(
+      &JsonStateCrawler::new(&config).ok().unwrap(),
+      "jsonstate",
+      "https://www.example-portal.de/suche/muenchen/wohnung-mieten",
+    );
+  }
+}
@@ -0,0 +1,246 @@
+extern crate kuchiki;
+extern crate std;
+
+use super::{Context, Crawler, Error};
+use crate::crawlers::Metadata;
+use crate::models::{Currency, PropertyData};
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use kuchiki::{ElementData, NodeDataRef, NodeRef};
+use url::Url;
+
+pub struct Kleinanzeigen {}
+
+impl Kleinanzeigen {
+  /// Reads the date an ad has been posted, which is given as "Heute, 14:32", "Gestern, 09:10" or "27.05.2024".
+  fn parse_posting_date(text: &str, fetched: DateTime<Local>) -> Option<NaiveDate> {
+    let text = text.trim().to_lowercase();
+    if text.starts_with("heute") {
+      Some(fetched.date().naive_local())
+    } else if text.starts_with("gestern") {
+      Some((fetched - Duration::days(1)).date().naive_local())
+    } else {
+      NaiveDate::parse_from_str(&text, "%d.%m.%Y").ok()
+    }
+  }
+}
+
+impl Crawler for Kleinanzeigen {
+  fn metadata(&self) -> Metadata {
+    Metadata {
+      name: String::from("kleinanzeigen"),
+      encoding: encoding_rs::UTF_8,
+    }
+  }
+
+  fn selector(&self) -> &str {
+    "article.aditem[data-adid]"
+  }
+
+  fn next_page(&self, document: &NodeRef, url: &Url, page: u32) -> Option<String> {
+    // the last page has no link to a next one
+    document.select_first(".pagination-next").ok()?;
+    // the page is a path segment in front of the category, eg. /s-wohnung-mieten/muenchen/seite:2/c203l6411
+    let mut segments: Vec<&str> = url
+      .path_segments()?
+      .filter(|segment| !segment.starts_with("seite:"))
+      .collect();
+    let category = segments.pop()?;
+    let next_page = format!("seite:{}", page + 1);
+    segments.push(&next_page);
+    segments.push(category);
+    let mut next = url.clone();
+    next.set_path(&segments.join("/"));
+    Some(next.to_string())
+  }
+
+  fn transform_result(
+    &self,
+    context: &Context,
+    result: NodeDataRef<ElementData>,
+  ) -> Result<PropertyData, Error> {
+    let externalid = Self::get_attr(&result, None, "data-adid")?;
+    let link = Self::get_attr(&result, None, "data-href")?;
+    let title = Self::get_text(&result, ".aditem-main--middle h2")?
+      .trim()
+      .to_string();
+    let address = Self::get_text(&result, ".aditem-main--top--left")?
+      .trim()
+      .to_string();
+    let price = Self::get_text(&result, ".aditem-main--middle--price-shipping--price")?;
+
+    // size and rooms are given as tags in no particular order, eg. "62 m²", "2 Zi." or "Grundstück 480 m²"
+    let facts = Self::get_texts(&result, ".aditem-main--bottom .simpletag")?;
+    let find_fact = |matches: &dyn Fn(&str) -> bool| {
+      facts
+        .iter()
+        .find(|fact| matches(fact))
+        .map(|fact| fact.to_owned())
+    };
+    let squaremeters = find_fact(&|fact| fact.contains("m²") && !fact.contains("Grundstück"))
+      .ok_or_else(|| Error::Incomplete(String::from("no living space")))?;
+    let rooms = find_fact(&|fact| fact.contains("Zi"))
+      .ok_or_else(|| Error::Incomplete(String::from("no rooms")))?;
+    let plot_squaremeters =
+      find_fact(&|fact| fact.contains("Grundstück")).and_then(|fact| Self::parse_number(fact).ok());
+
+    let mut tags = vec![];
+    // commercial providers are marked as "PRO"
+    if Self::get_optional_text(&result, ".badge-hint-pro-small-srp").is_some() {
+      tags.push(String::from("Gewerblich"));
+    } else {
+      tags.push(String::from("Privat"));
+    }
+    let posted = Self::get_optional_text(&result, ".aditem-main--top--right")
+      .and_then(|text| Self::parse_posting_date(&text, context.fetched));
+    if let Some(posted) = posted {
+      tags.push(format!("Eingestellt am {}", posted.format("%d.%m.%Y")));
+    }
+
+    Ok(PropertyData {
+      cold_rent: Some(Self::parse_number(price)?),
+      warm_rent: None,
+      service_charges: None,
+      deposit: None,
+      purchase_price: None,
+      currency: Currency::Eur,
+      squaremeters: Self::parse_number(squaremeters)?,
+      plot_squaremeters,
+      address,
+      title,
+      rooms: Self::parse_number(rooms)?,
+      url: context.absolute_url(&link)?,
+      externalid,
+      property_type: context.config.property_type.clone(),
+      contract_type: context.config.contract_type.clone(),
+      tags,
+      location: None,
+      available_from: None,
+      available_until: None,
+      room: None,
+      details: None,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Kleinanzeigen;
+  use crate::crawlers::fixtures::assert_snapshot;
+  use crate::crawlers::{Config, Context, Crawler};
+  use crate::models::{ContractType, PropertyType};
+  use chrono::{Local, NaiveDate, TimeZone};
+  use kuchiki::traits::*;
+  use url::Url;
+
+  #[test]
+  fn transform_recorded_results() {
+    assert_snapshot(
+      &Kleinanzeigen {},
+      "kleinanzeigen",
+      "https://www.kleinanzeigen.de/s-wohnung-mieten/muenchen/c203l6411",
+    );
+  }
+
+  #[test]
+  fn tag_provider_and_posting_date() {
+    // GIVEN
+    let config = Config {
+      address: String::from("https://www.kleinanzeigen.de/s-haus-kaufen/muenchen/c208l6411"),
+      city: String::from("München"),
+      crawler: String::from("kleinanzeigen"),
+      property_type: PropertyType::House,
+      contract_type: ContractType::Buy,
+      max_pages: 1,
+      incremental: true,
+      encoding: None,
+      interval: None,
+      cron: None,
+      jitter: 0,
+      quiet_hours: None,
+    };
+    let url = Url::parse(&config.address).unwrap();
+    let context = Context {
+      config: &config,
+      url: &url,
+      page: 1,
+      fetched: Local.ymd(2024, 5, 28).and_hms(8, 30, 0),
+    };
+    let document = kuchiki::parse_html().one(
+      r#"<article class="aditem" data-adid="2699000222" data-href="/s-anzeige/reihenhaus/2699000222-208-6411">
+        <div class="aditem-main--top--left">81247 Obermenzing</div>
+        <div class="aditem-main--top--right">Gestern, 18:45</div>
+        <div class="aditem-main--middle"><h2>Reihenhaus mit Garten</h2>
+          <p class="aditem-main--middle--price-shipping--price">890.000 € VB</p></div>
+        <div class="aditem-main--bottom">
+          <span class="simpletag">Grundstück 230 m²</span><span class="simpletag">128 m²</span><span class="simpletag">5 Zi.</span>
+          <span class="badge-hint-pro-small-srp">PRO</span>
+        </div>
+      </article>"#,
+    );
+    let crawler = Kleinanzeigen {};
+    let result = document.select_first(crawler.selector()).unwrap();
+
+    // WHEN
+    let data = crawler.transform_result(&context, result).ok().unwrap();
+
+    // THEN
+    assert_eq!(data.tags, vec!["Gewerblich", "Eingestellt am 27.05.2024"]);
+    assert_eq!(data.squaremeters, 128.0);
+    assert_eq!(data.plot_squaremeters, Some(230.0));
+    assert_eq!(data.property_type, PropertyType::House);
+    assert_eq!(
+      data.url,
+      "https://www.kleinanzeigen.de/s-anzeige/reihenhaus/2699000222-208-6411"
+    );
+  }
+
+  #[test]
+  fn parse_relative_posting_date() {
+    // GIVEN
+    let fetched = Local.ymd(2024, 5, 28).and_hms(8, 30, 0);
+
+    // THEN
+    assert_eq!(
+      Kleinanzeigen::parse_posting_date(" Heute, 07:12", fetched),
+      Some(NaiveDate::from_ymd(2024, 5, 28))
+    );
+    assert_eq!(
+      Kleinanzeigen::parse_posting_date("Gestern, 21:05", fetched),
+      Some(NaiveDate::from_ymd(2024, 5, 27))
+    );
+    assert_eq!(
+      Kleinanzeigen::parse_posting_date("26.05.2024", fetched),
+      Some(NaiveDate::from_ymd(2024, 5, 26))
+    );
+  }
+
+  #[test]
+  fn next_page_in_path() {
+    // GIVEN
+    let crawler = Kleinanzeigen {};
+    let document = kuchiki::parse_html().one(
+      r#"<div class="pagination"><a class="pagination-next" href="/s-wohnung-mieten/muenchen/seite:2/c203l6411"></a></div>"#,
+    );
+    let last_page = kuchiki::parse_html().one(r#"<div class="pagination"></div>"#);
+    let first =
+      Url::parse("https://www.kleinanzeigen.de/s-wohnung-mieten/muenchen/c203l6411").unwrap();
+    let second =
+      Url::parse("https://www.kleinanzeigen.de/s-wohnung-mieten/muenchen/seite:2/c203l6411")
+        .unwrap();
+
+    // THEN
+    assert_eq!(
+      crawler.next_page(&document, &first, 1),
+      Some(String::from(
+        "https://www.kleinanzeigen.de/s-wohnung-mieten/muenchen/seite:2/c203l6411"
+      ))
+    );
+    assert_eq!(
+      crawler.next_page(&document, &second, 2),
+      Some(String::from(
+        "https://www.kleinanzeigen.de/s-wohnung-mieten/muenchen/seite:3/c203l6411"
+      ))
+    );
+    assert_eq!(crawler.next_page(&last_page, &second, 2), None);
+  }
+}
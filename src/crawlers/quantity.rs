@@ -0,0 +1,283 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_derive::{Deserialize, Serialize};
+
+/// Conventions of writing numbers on a portal.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Locale {
+  /// 1.234,56
+  De,
+  /// 1.234,56
+  At,
+  /// 1'234.56
+  Ch,
+  /// 1,234.56
+  En,
+}
+
+impl Locale {
+  fn decimal_separator(self) -> char {
+    match self {
+      Locale::De | Locale::At => ',',
+      Locale::Ch | Locale::En => '.',
+    }
+  }
+}
+
+impl std::str::FromStr for Locale {
+  type Err = ();
+  fn from_str(s: &str) -> Result<Self, ()> {
+    match s {
+      "de" => Ok(Locale::De),
+      "at" => Ok(Locale::At),
+      "ch" => Ok(Locale::Ch),
+      "en" => Ok(Locale::En),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Unit {
+  Euro,
+  Franc,
+  SquareMeters,
+  Rooms,
+}
+
+/// A number read from a portal, which may be given as a range or not at all.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Quantity {
+  pub lower: Option<f32>,
+  pub upper: Option<f32>,
+  pub unit: Option<Unit>,
+  /// the value is only given on request, e.g. "Preis auf Anfrage"
+  pub on_request: bool,
+}
+
+impl Quantity {
+  /// The single value of this quantity, which is the lower bound of a range.
+  pub fn value(&self) -> Option<f32> {
+    self.lower.or(self.upper)
+  }
+}
+
+const ON_REQUEST: [&str; 4] = [
+  "auf anfrage",
+  "on request",
+  "upon request",
+  "on application",
+];
+
+/// Reads a quantity like "ab 1.200 €", "1,5 Zi.", "CHF 1'850.–" or "1.200 - 1.500 €" from a text.
+pub fn parse(text: &str, locale: Locale) -> Quantity {
+  lazy_static! {
+    // remarks in parentheses, e.g. "85 m² (Nutzfläche 100 m²)"
+    static ref REMARK_REGEX: Regex = Regex::new(r"\([^)]*\)").unwrap();
+    static ref NUMBER_REGEX: Regex =
+      Regex::new(r"(\d(?:[\d.,'’]|[ \u{a0}]\d{3}\b)*)\s*(k\b|tsd\b\.?|tausend\b|mio\b\.?)?").unwrap();
+  }
+  let lowercase = text.to_lowercase();
+  let text = REMARK_REGEX.replace_all(&lowercase, " ");
+
+  let mut numbers = NUMBER_REGEX.captures_iter(&text).filter_map(|captures| {
+    let number = parse_number(captures.get(1)?.as_str(), locale)?;
+    let factor = match captures.get(2).map(|suffix| suffix.as_str()) {
+      Some(suffix) if suffix.starts_with("mio") => 1_000_000.0,
+      Some(_) => 1000.0,
+      None => 1.0,
+    };
+    let whole = captures.get(0)?;
+    Some((number * factor, whole.start(), whole.end()))
+  });
+  let first = numbers.next();
+  let second = numbers.next();
+
+  let mut quantity = Quantity {
+    unit: parse_unit(&text),
+    on_request: ON_REQUEST.iter().any(|marker| text.contains(marker)),
+    ..Default::default()
+  };
+  if let Some((value, start, end)) = first {
+    let before = text[..start].trim_end();
+    let range = second.filter(|(_, second_start, _)| {
+      let between = text[end..*second_start].trim();
+      ["-", "–", "bis", "to"].contains(&between)
+    });
+    if let Some((upper, _, _)) = range {
+      quantity.lower = Some(value);
+      quantity.upper = Some(upper);
+    } else if ["ab", "von", "from", "min.", "mind."]
+      .iter()
+      .any(|prefix| before.ends_with(prefix))
+    {
+      quantity.lower = Some(value);
+    } else if ["bis", "up to", "max."]
+      .iter()
+      .any(|prefix| before.ends_with(prefix))
+    {
+      quantity.upper = Some(value);
+    } else {
+      quantity.lower = Some(value);
+      quantity.upper = Some(value);
+    }
+  }
+  quantity
+}
+
+/// Converts a number with separators into a float.
+/// Apostrophes and spaces always separate thousands, a dot or comma that is the only separator
+/// is taken as decimal separator, unless it is the locale's thousands separator followed by three digits.
+fn parse_number(number: &str, locale: Locale) -> Option<f32> {
+  let number: String = number
+    .trim_end_matches(&['.', ','][..])
+    .chars()
+    .filter(|c| !['\'', '’', ' ', '\u{a0}'].contains(c))
+    .collect();
+  let separators: Vec<(usize, char)> = number
+    .char_indices()
+    .filter(|(_, c)| *c == '.' || *c == ',')
+    .collect();
+  let decimal_position = match separators.as_slice() {
+    [] => None,
+    [(position, separator)] => {
+      let decimals = number.len() - position - 1;
+      if *separator == locale.decimal_separator() || decimals != 3 {
+        Some(*position)
+      } else {
+        None
+      }
+    }
+    [.., (last_position, last)] => {
+      if separators.iter().all(|(_, separator)| separator == last) {
+        None
+      } else {
+        Some(*last_position)
+      }
+    }
+  };
+  let normalized: String = number
+    .char_indices()
+    .filter_map(|(position, c)| match c {
+      '.' | ',' if Some(position) == decimal_position => Some('.'),
+      '.' | ',' => None,
+      c => Some(c),
+    })
+    .collect();
+  normalized.parse().ok()
+}
+
+fn parse_unit(text: &str) -> Option<Unit> {
+  lazy_static! {
+    static ref UNIT_REGEX: Regex = Regex::new(concat!(
+      r"(€|\beur\b|\beuro\b)|",
+      r"(\bchf\b|\bfr\.)|",
+      r"(m²|\bm2\b|\bqm\b|\bsqm\b)|",
+      r"(\bzi\b|\bzimmer\b|\brooms?\b)"
+    ))
+    .unwrap();
+  }
+  UNIT_REGEX.captures(text).map(|captures| {
+    if captures.get(1).is_some() {
+      Unit::Euro
+    } else if captures.get(2).is_some() {
+      Unit::Franc
+    } else if captures.get(3).is_some() {
+      Unit::SquareMeters
+    } else {
+      Unit::Rooms
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{parse, Locale, Quantity, Unit};
+
+  #[test]
+  fn parse_price_with_thousands() {
+    assert_eq!(
+      parse("1.234,50 €", Locale::De),
+      Quantity {
+        lower: Some(1234.5),
+        upper: Some(1234.5),
+        unit: Some(Unit::Euro),
+        on_request: false,
+      }
+    );
+  }
+
+  #[test]
+  fn parse_lower_bound() {
+    // WHEN
+    let quantity = parse("ab 1.200 €", Locale::De);
+
+    // THEN
+    assert_eq!(quantity.lower, Some(1200.0));
+    assert_eq!(quantity.upper, None);
+    assert_eq!(quantity.value(), Some(1200.0));
+  }
+
+  #[test]
+  fn parse_range() {
+    // WHEN
+    let quantity = parse("1.200 - 1.500 € Kaltmiete", Locale::De);
+
+    // THEN
+    assert_eq!(quantity.lower, Some(1200.0));
+    assert_eq!(quantity.upper, Some(1500.0));
+  }
+
+  #[test]
+  fn parse_rooms() {
+    // WHEN
+    let quantity = parse("1,5 Zi.", Locale::De);
+
+    // THEN
+    assert_eq!(quantity.value(), Some(1.5));
+    assert_eq!(quantity.unit, Some(Unit::Rooms));
+  }
+
+  #[test]
+  fn ignore_remarks() {
+    // WHEN
+    let quantity = parse("ca. 85 m² (Nutzfläche 100 m²)", Locale::De);
+
+    // THEN
+    assert_eq!(quantity.value(), Some(85.0));
+    assert_eq!(quantity.upper, Some(85.0));
+    assert_eq!(quantity.unit, Some(Unit::SquareMeters));
+  }
+
+  #[test]
+  fn parse_abbreviated_thousands() {
+    assert_eq!(parse("1.2k", Locale::De).value(), Some(1200.0));
+    assert_eq!(parse("1,2 Mio. €", Locale::De).value(), Some(1_200_000.0));
+  }
+
+  #[test]
+  fn parse_swiss_price() {
+    // WHEN
+    let quantity = parse("CHF 1'850.–", Locale::Ch);
+
+    // THEN
+    assert_eq!(quantity.value(), Some(1850.0));
+    assert_eq!(quantity.unit, Some(Unit::Franc));
+  }
+
+  #[test]
+  fn parse_english_number() {
+    assert_eq!(parse("1,250.75 EUR", Locale::En).value(), Some(1250.75));
+    assert_eq!(parse("1,250 sqm", Locale::En).value(), Some(1250.0));
+  }
+
+  #[test]
+  fn parse_price_on_request() {
+    // WHEN
+    let quantity = parse("Preis auf Anfrage", Locale::At);
+
+    // THEN
+    assert!(quantity.on_request);
+    assert_eq!(quantity.value(), None);
+  }
+}
@@ -0,0 +1,147 @@
+use crate::configuration::ApplicationConfig;
+use crate::crawlers::{self, Config, Crawler, Error, Health, HttpClient};
+use crate::filters::Filter;
+use crate::models::Property;
+use crate::shutdown::Shutdown;
+use futures::channel::mpsc::UnboundedSender;
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
+use url::Url;
+
+/// Crawls all watchers of a run concurrently.
+/// At most `scheduler.concurrency` watchers are crawled at the same time and at most
+/// `scheduler.domain_concurrency` of them on the same domain, so a slow portal only
+/// holds up its own watchers. Watchers that exceed `scheduler.watcher_timeout` are aborted.
+/// Once a shutdown has been requested, watchers that did not start yet are skipped.
+/// The result of each watcher is sent as soon as it finished, so its properties can be processed
+/// while other watchers are still crawling. The channel is closed once all watchers finished.
+pub async fn crawl(
+  app_config: &ApplicationConfig,
+  watchers: Vec<Config>,
+  http: &HttpClient,
+  filters: &Mutex<Vec<Box<dyn Filter>>>,
+  shutdown: &Shutdown,
+  results: UnboundedSender<(Config, Health, Vec<Property>)>,
+) {
+  let config = &app_config.scheduler;
+  let crawlers = crawlers::get_crawlers(app_config);
+  let global = Semaphore::new(config.concurrency.max(1));
+  let domains: HashMap<String, Semaphore> = watchers
+    .iter()
+    .map(|watcher| {
+      (
+        domain(&watcher.address),
+        Semaphore::new(config.domain_concurrency.max(1)),
+      )
+    })
+    .collect();
+
+  let crawlers = &crawlers;
+  let global = &global;
+  let domains = &domains;
+  let results = &results;
+  watchers
+    .into_iter()
+    .map(|watcher| async move {
+      // waiting for the domain first keeps global slots free for other portals
+      let _domain_permit = match domains.get(&domain(&watcher.address)) {
+        Some(semaphore) => Some(semaphore.acquire().await),
+        None => None,
+      };
+      let _permit = global.acquire().await;
+      if shutdown.is_requested() {
+        println!(
+          "skipping {} watcher {}, shutting down.",
+          watcher.crawler, watcher.city
+        );
+        return;
+      }
+      let result = process_watcher(app_config, crawlers, watcher, http, filters).await;
+      if results.unbounded_send(result).is_err() {
+        eprintln!("Result of watcher could not be passed on, processing already stopped.");
+      }
+    })
+    .collect::<FuturesUnordered<_>>()
+    .for_each(|_| async {})
+    .await
+}
+
+async fn process_watcher(
+  app_config: &ApplicationConfig,
+  crawlers: &Vec<Box<dyn Crawler>>,
+  watcher: Config,
+  http: &HttpClient,
+  filters: &Mutex<Vec<Box<dyn Filter>>>,
+) -> (Config, Health, Vec<Property>) {
+  let mut health = Health::default();
+  let properties = match crawlers::get_crawler(&watcher.crawler, crawlers) {
+    Ok(crawler) => {
+      println!(
+        "processing '{}' for {} ...",
+        crawler.metadata().name,
+        watcher.city
+      );
+      let timeout = app_config.scheduler.watcher_timeout;
+      let execution = crawlers::execute(app_config, http, &watcher, crawler, filters, &mut health);
+      match tokio::time::timeout(Duration::from_secs(timeout), execution).await {
+        Ok(Ok(properties)) => {
+          if app_config.test {
+            for property in &properties {
+              println!("parsed property: {:?}", property);
+            }
+          }
+          properties
+        }
+        Ok(Err(error)) => {
+          eprintln!("error: {}", error);
+          health.fail(error);
+          vec![]
+        }
+        Err(_) => {
+          let error = Error::Timeout(timeout);
+          eprintln!("error: {}", error);
+          health.fail(error);
+          vec![]
+        }
+      }
+    }
+    Err(e) => {
+      eprintln!("config could not be processed: {}", e);
+      health.fail(e);
+      vec![]
+    }
+  };
+  (watcher, health, properties)
+}
+
+/// Host of the watcher's address, which is shared by all watchers of a portal.
+fn domain(address: &str) -> String {
+  Url::parse(address)
+    .ok()
+    .and_then(|url| url.host_str().map(String::from))
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::domain;
+
+  #[test]
+  fn watchers_are_grouped_by_host() {
+    // GIVEN
+    let rent = "https://www.immobilienscout24.de/Suche/de/bayern/muenchen/wohnung-mieten";
+    let buy = "https://www.immobilienscout24.de/Suche/de/bayern/muenchen/wohnung-kaufen";
+    let other = "https://www.immowelt.de/liste/muenchen/wohnungen/mieten";
+
+    // WHEN
+    let domains = (domain(rent), domain(buy), domain(other));
+
+    // THEN
+    assert_eq!(domains.0, "www.immobilienscout24.de");
+    assert_eq!(domains.0, domains.1);
+    assert_ne!(domains.0, domains.2);
+    assert_eq!(domain("not a url"), "");
+  }
+}
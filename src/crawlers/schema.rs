@@ -0,0 +1,388 @@
+extern crate kuchiki;
+extern crate serde_json;
+
+use crate::crawlers::quantity::{self, Locale};
+use crate::models::Location;
+use kuchiki::iter::NodeIterator;
+use kuchiki::NodeRef;
+use serde_json::{Map, Value};
+
+/// Types of entities, that describe a property or an offer of a property.
+const TYPES: [&str; 4] = ["Offer", "Apartment", "House", "Accommodation"];
+
+/// Properties that link an offer to the offered property and vice versa.
+const RELATIONS: [&str; 3] = ["itemOffered", "offers", "about"];
+
+/// Key under which microdata keeps a value, that is the visible text of an element
+/// and therefore written in the portal's locale, unlike attributes and JSON-LD.
+const VISIBLE_TEXT: &str = "@text";
+
+/// A property as described by the schema.org vocabulary of a portal.
+/// An offer and the property it offers are merged into one listing.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Listing {
+  pub name: Option<String>,
+  pub url: Option<String>,
+  pub identifier: Option<String>,
+  pub price: Option<f32>,
+  pub currency: Option<String>,
+  pub floor_size: Option<f32>,
+  pub rooms: Option<f32>,
+  pub address: Option<String>,
+  pub location: Option<Location>,
+}
+
+impl Listing {
+  fn from_entity(entity: &Value, locale: Locale) -> Self {
+    let mut listing = Listing::default();
+    listing.fill(entity, locale);
+    for relation in RELATIONS.iter() {
+      if let Some(related) = first(entity.get(*relation)) {
+        listing.fill(related, locale);
+      }
+    }
+    listing
+  }
+
+  /// Completes the listing with the values of the entity, that are still missing.
+  fn fill(&mut self, entity: &Value, locale: Locale) {
+    let specification = first(entity.get("priceSpecification"));
+    self.name = self.name.take().or_else(|| text(entity.get("name")));
+    self.url = self.url.take().or_else(|| text(entity.get("url")));
+    self.identifier = self
+      .identifier
+      .take()
+      .or_else(|| text(entity.get("identifier")))
+      .or_else(|| text(entity.get("sku")));
+    self.price = self
+      .price
+      .or_else(|| number(entity.get("price"), locale))
+      .or_else(|| number(specification.and_then(|spec| spec.get("price")), locale));
+    self.currency = self
+      .currency
+      .take()
+      .or_else(|| text(entity.get("priceCurrency")))
+      .or_else(|| text(specification.and_then(|spec| spec.get("priceCurrency"))));
+    self.floor_size = self
+      .floor_size
+      .or_else(|| number(entity.get("floorSize"), locale));
+    self.rooms = self
+      .rooms
+      .or_else(|| number(entity.get("numberOfRooms"), locale));
+    self.address = self
+      .address
+      .take()
+      .or_else(|| address(entity.get("address")));
+    self.location = self.location.take().or_else(|| geo(entity.get("geo")));
+  }
+}
+
+/// Finds all listings within the node, that are described by microdata or JSON-LD.
+pub fn extract(node: &NodeRef, locale: Locale) -> Vec<Listing> {
+  let mut listings = from_microdata(node, locale);
+  listings.extend(from_json_ld(node, locale));
+  listings
+}
+
+/// Finds all listings described by `itemscope`, `itemtype` and `itemprop` attributes.
+pub fn from_microdata(node: &NodeRef, locale: Locale) -> Vec<Listing> {
+  let items: Vec<Value> = node
+    .inclusive_descendants()
+    .elements()
+    .filter(|element| {
+      let attributes = element.attributes.borrow();
+      // properties of other items are read along with their item
+      attributes.get("itemscope").is_some()
+        && (attributes.get("itemprop").is_none() || element.as_node() == node)
+    })
+    .map(|element| microdata_item(element.as_node()))
+    .collect();
+  let mut listings = vec![];
+  collect_listings(&Value::Array(items), locale, &mut listings);
+  listings
+}
+
+/// Finds all listings described by `<script type="application/ld+json">` elements.
+pub fn from_json_ld(node: &NodeRef, locale: Locale) -> Vec<Listing> {
+  let mut listings = vec![];
+  if let Ok(scripts) = node.select("script[type='application/ld+json']") {
+    for script in scripts {
+      match serde_json::from_str::<Value>(&script.text_contents()) {
+        Ok(data) => collect_listings(&data, locale, &mut listings),
+        Err(e) => println!("Could not parse JSON-LD: {}", e),
+      }
+    }
+  }
+  listings
+}
+
+fn collect_listings(value: &Value, locale: Locale, listings: &mut Vec<Listing>) {
+  match value {
+    Value::Array(values) => values
+      .iter()
+      .for_each(|value| collect_listings(value, locale, listings)),
+    Value::Object(entries) => {
+      if types(value).iter().any(|kind| TYPES.contains(kind)) {
+        listings.push(Listing::from_entity(value, locale));
+      } else {
+        entries
+          .values()
+          .for_each(|value| collect_listings(value, locale, listings));
+      }
+    }
+    _ => (),
+  }
+}
+
+/// Converts an item into the structure it would have in JSON-LD.
+fn microdata_item(node: &NodeRef) -> Value {
+  let mut item = Map::new();
+  let itemtype = node.as_element().and_then(|element| {
+    element
+      .attributes
+      .borrow()
+      .get("itemtype")
+      .map(String::from)
+  });
+  if let Some(itemtype) = itemtype {
+    item.insert(String::from("@type"), Value::String(itemtype));
+  }
+  collect_properties(node, &mut item);
+  Value::Object(item)
+}
+
+fn collect_properties(node: &NodeRef, item: &mut Map<String, Value>) {
+  for child in node.children() {
+    let (scope, names) = match child.as_element() {
+      Some(element) => {
+        let attributes = element.attributes.borrow();
+        (
+          attributes.get("itemscope").is_some(),
+          attributes.get("itemprop").map(String::from),
+        )
+      }
+      None => continue,
+    };
+    match names {
+      Some(names) => {
+        let value = if scope {
+          microdata_item(&child)
+        } else {
+          property_value(&child)
+        };
+        for name in names.split_whitespace() {
+          item.entry(name).or_insert_with(|| value.clone());
+        }
+        if !scope {
+          collect_properties(&child, item);
+        }
+      }
+      // a nested item, that is not a property of this one
+      None if scope => (),
+      None => collect_properties(&child, item),
+    }
+  }
+}
+
+fn property_value(node: &NodeRef) -> Value {
+  let element = match node.as_element() {
+    Some(element) => element,
+    None => return Value::String(String::new()),
+  };
+  let attributes = element.attributes.borrow();
+  let attribute = match &*element.name.local {
+    "meta" => "content",
+    "a" | "link" | "area" => "href",
+    "img" | "audio" | "video" | "source" | "iframe" | "embed" => "src",
+    "object" => "data",
+    "data" | "meter" => "value",
+    "time" => "datetime",
+    _ => "content",
+  };
+  match attributes.get(attribute) {
+    Some(value) => Value::String(value.trim().to_owned()),
+    None => {
+      let mut visible = Map::new();
+      let text = node.text_contents().trim().to_owned();
+      visible.insert(String::from(VISIBLE_TEXT), Value::String(text));
+      Value::Object(visible)
+    }
+  }
+}
+
+/// Names of the entity's types without their vocabulary, eg. `Offer` for `http://schema.org/Offer`.
+fn types(entity: &Value) -> Vec<&str> {
+  let names = match entity.get("@type") {
+    Some(Value::String(name)) => vec![name.as_str()],
+    Some(Value::Array(names)) => names.iter().filter_map(|name| name.as_str()).collect(),
+    _ => vec![],
+  };
+  names
+    .into_iter()
+    .flat_map(|names| names.split_whitespace())
+    .map(|name| name.rsplit(&['/', ':', '#'][..]).next().unwrap_or(name))
+    .collect()
+}
+
+fn first(value: Option<&Value>) -> Option<&Value> {
+  match value {
+    Some(Value::Array(values)) => values.first(),
+    value => value,
+  }
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+  match first(value)? {
+    Value::String(text) => Some(text.trim().to_owned()).filter(|text| !text.is_empty()),
+    Value::Number(number) => Some(number.to_string()),
+    // eg. a PropertyValue or QuantitativeValue
+    entity @ Value::Object(_) => text(entity.get(VISIBLE_TEXT).or_else(|| entity.get("value"))),
+    _ => None,
+  }
+}
+
+/// Reads a number, which always uses a decimal point, unless it is the visible text of an element.
+fn number(value: Option<&Value>, locale: Locale) -> Option<f32> {
+  match first(value)? {
+    Value::Number(number) => number.as_f64().map(|number| number as f32),
+    Value::String(text) => text
+      .trim()
+      .parse()
+      .ok()
+      // eg. "78 m²"
+      .or_else(|| quantity::parse(text, Locale::En).value()),
+    entity @ Value::Object(_) => match entity.get(VISIBLE_TEXT) {
+      Some(Value::String(text)) => quantity::parse(text, locale).value(),
+      _ => number(entity.get("value"), locale),
+    },
+    _ => None,
+  }
+}
+
+fn address(value: Option<&Value>) -> Option<String> {
+  match first(value)? {
+    entity @ Value::Object(_) if entity.get(VISIBLE_TEXT).is_none() => {
+      let locality = [
+        text(entity.get("postalCode")),
+        text(entity.get("addressLocality")),
+      ]
+      .iter()
+      .flatten()
+      .cloned()
+      .collect::<Vec<_>>()
+      .join(" ");
+      let parts: Vec<String> = vec![text(entity.get("streetAddress")), Some(locality)]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .collect();
+      Some(parts.join(", ")).filter(|address| !address.is_empty())
+    }
+    value => text(Some(value)),
+  }
+}
+
+fn geo(value: Option<&Value>) -> Option<Location> {
+  // coordinates always use a decimal point, independent of the portal's locale
+  let coordinate = |name: &str| match first(value)?.get(name)? {
+    Value::Number(number) => number.as_f64().map(|number| number as f32),
+    Value::String(text) => text.trim().parse().ok(),
+    _ => None,
+  };
+  Some(Location {
+    latitude: coordinate("latitude")?,
+    longitude: coordinate("longitude")?,
+    uncertainty: 0.0,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{extract, Listing};
+  use crate::crawlers::quantity::Locale;
+  use kuchiki::traits::*;
+
+  #[test]
+  fn read_microdata_offer() {
+    // GIVEN
+    let document = kuchiki::parse_html().one(
+      r#"<div itemscope itemtype="http://schema.org/Offer">
+        <h2 itemprop="name">3-Zimmer-Wohnung</h2>
+        <div itemprop="priceSpecification" itemscope itemtype="http://schema.org/UnitPriceSpecification">
+          <meta itemprop="price" content="1450.50"><meta itemprop="priceCurrency" content="EUR">
+        </div>
+        <div itemprop="itemOffered" itemscope itemtype="http://schema.org/Apartment">
+          <div itemprop="floorSize" itemscope itemtype="http://schema.org/QuantitativeValue">
+            <meta itemprop="value" content="34.5">
+          </div>
+          <span itemprop="numberOfRooms">1,5</span>
+          <div itemprop="address" itemscope itemtype="http://schema.org/PostalAddress">
+            <span itemprop="streetAddress">Dachauer Straße 5</span>,
+            <span itemprop="postalCode">80335</span> <span itemprop="addressLocality">München</span>
+          </div>
+        </div>
+        <div itemscope itemtype="http://schema.org/Organization"><span itemprop="name">Makler</span></div>
+      </div>"#,
+    );
+
+    // WHEN
+    let listings = extract(&document, Locale::De);
+
+    // THEN
+    assert_eq!(
+      listings,
+      vec![Listing {
+        name: Some(String::from("3-Zimmer-Wohnung")),
+        price: Some(1450.5),
+        currency: Some(String::from("EUR")),
+        floor_size: Some(34.5),
+        rooms: Some(1.5),
+        address: Some(String::from("Dachauer Straße 5, 80335 München")),
+        ..Default::default()
+      }]
+    );
+  }
+
+  #[test]
+  fn read_json_ld_graph() {
+    // GIVEN
+    let document = kuchiki::parse_html().one(
+      r#"<html><head><script type="application/ld+json">
+        {
+          "@context": "https://schema.org",
+          "@graph": [
+            { "@type": "BreadcrumbList", "itemListElement": [] },
+            {
+              "@type": ["Apartment", "Accommodation"],
+              "identifier": "4711",
+              "url": "https://www.example.com/expose/4711",
+              "floorSize": { "@type": "QuantitativeValue", "value": 78, "unitCode": "MTK" },
+              "numberOfRooms": "2.5",
+              "address": "Leopoldstraße 12, München",
+              "geo": { "@type": "GeoCoordinates", "latitude": "48.1589", "longitude": 11.585 },
+              "offers": { "@type": "Offer", "price": 1890, "priceCurrency": "EUR" }
+            }
+          ]
+        }
+      </script></head></html>"#,
+    );
+
+    // WHEN
+    let listings = extract(&document, Locale::En);
+
+    // THEN
+    assert_eq!(listings.len(), 1);
+    let listing = &listings[0];
+    assert_eq!(listing.identifier, Some(String::from("4711")));
+    assert_eq!(listing.price, Some(1890.0));
+    assert_eq!(listing.floor_size, Some(78.0));
+    assert_eq!(listing.rooms, Some(2.5));
+    assert_eq!(
+      listing.address,
+      Some(String::from("Leopoldstraße 12, München"))
+    );
+    let location = listing.location.as_ref().unwrap();
+    assert_eq!(location.latitude, 48.1589);
+    assert_eq!(location.longitude, 11.585);
+  }
+}
@@ -3,10 +3,9 @@ extern crate regex;
 extern crate reqwest;
 extern crate std;
 
-use super::{Crawler, Error};
+use super::{Context, Crawler, Error};
 use crate::crawlers::Metadata;
-use crate::models::Encoding;
-use crate::models::{ContractType, PropertyData, PropertyType};
+use crate::models::{Currency, PropertyData, PropertyType};
 use kuchiki::{ElementData, NodeDataRef};
 use std::ops::Deref;
 
@@ -26,15 +25,19 @@ impl Crawler for Sueddeutsche {
   fn metadata(&self) -> Metadata {
     Metadata {
       name: String::from("sueddeutsche"),
-      encoding: Encoding::Latin1,
+      encoding: encoding_rs::WINDOWS_1252,
     }
   }
 
-  fn selector(&self) -> &'static str {
+  fn selector(&self) -> &str {
     "#idHitContent .hitRow"
   }
 
-  fn transform_result<'a>(&self, result: NodeDataRef<ElementData>) -> Result<PropertyData, Error> {
+  fn transform_result(
+    &self,
+    context: &Context,
+    result: NodeDataRef<ElementData>,
+  ) -> Result<PropertyData, Error> {
     let hit_rooms_div_text = Self::get_text(&result, ".hitRoomsDiv")?;
     let hit_rooms_div_elements: Vec<&str> = hit_rooms_div_text.split(", ").collect();
     let squaremeters_opt: Option<&&str> = hit_rooms_div_elements.get(0);
@@ -56,30 +59,72 @@ impl Crawler for Sueddeutsche {
     let rent = Self::get_text(&result, ".hitPrice")?.replace("&nbsp;", " ");
 
     let externalid = Self::get_attr(&result, None, "id")?.replace("idHitRowList", "");
+    // exposes are located below the category and city of the search, eg. /Wohnungen/mieten/Muenchen
+    let category: Vec<&str> = context
+      .url
+      .path_segments()
+      .map(|segments| segments.take(3).collect())
+      .unwrap_or_default();
+    if category.len() < 3 {
+      return Err(Error::Incomplete(format!(
+        "no city within url '{}'",
+        context.url
+      )));
+    }
+    let object = match context.config.property_type {
+      PropertyType::Flat | PropertyType::Room => "Wohnung",
+      PropertyType::House => "Haus",
+    };
+    let url = context.absolute_url(&format!(
+      "/{}/{}/{}?comeFromTL=1",
+      category.join("/"),
+      object,
+      externalid
+    ))?;
 
     match (&squaremeters_opt, &rooms_opt) {
       (&Some(squaremeters), &Some(rooms)) => Ok(PropertyData {
-        price: Self::parse_number(rent)?,
+        cold_rent: Some(Self::parse_number(rent)?),
+        warm_rent: None,
+        service_charges: None,
+        deposit: None,
+        purchase_price: None,
+        currency: Currency::Eur,
         squaremeters: Self::parse_number(squaremeters.deref().to_owned())?,
         plot_squaremeters: None,
         address,
         title,
         rooms: Self::parse_number(rooms.deref().to_owned())?,
-        url: format!(
-          "https://immobilienmarkt.sueddeutsche.de/Wohnungen/mieten/Muenchen/Wohnung/{}?comeFromTL=1",
-          &externalid
-        ),
+        url,
         externalid,
-        property_type: PropertyType::Flat,
-        contract_type: ContractType::Rent,
+        property_type: context.config.property_type.clone(),
+        contract_type: context.config.contract_type.clone(),
         tags: vec![],
+        location: None,
+        available_from: None,
+        available_until: None,
+        room: None,
+        details: None,
       }),
-      _ => Err(Error {
-        message: format!(
-          "Information is incomplete: {:?}, {:?}!",
-          squaremeters_opt, rooms_opt
-        ),
-      }),
+      _ => Err(Error::Incomplete(format!(
+        "{:?}, {:?}!",
+        squaremeters_opt, rooms_opt
+      ))),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::Sueddeutsche;
+  use crate::crawlers::fixtures::assert_snapshot;
+
+  #[test]
+  fn transform_recorded_results() {
+    assert_snapshot(
+      &Sueddeutsche::new(),
+      "sueddeutsche",
+      "https://immobilienmarkt.sueddeutsche.de/Wohnungen/mieten/Muenchen",
+    );
+  }
+}
@@ -1,56 +1,194 @@
 extern crate kuchiki;
+extern crate regex;
 extern crate reqwest;
 extern crate std;
 
-use super::{Crawler, Error};
+use super::{Context, Crawler, Error};
 use crate::crawlers::Metadata;
-use crate::models::Encoding;
-use crate::models::{ContractType, PropertyData, PropertyType};
-use kuchiki::{ElementData, NodeDataRef};
+use crate::models::{ContractType, Currency, Gender, PropertyData, PropertyType, RoomDetails};
+use chrono::NaiveDate;
+use kuchiki::{ElementData, NodeDataRef, NodeRef};
+use lazy_static::lazy_static;
+use regex::Regex;
+use url::Url;
 
 pub struct WGGesucht {}
 
+impl WGGesucht {
+  fn get_date(result: &NodeDataRef<ElementData>, selector: &str) -> Option<NaiveDate> {
+    Self::get_optional_text(result, selector).and_then(|date| Self::parse_date(&date))
+  }
+
+  /// Reads the shared flat of a room, eg. "3er WG", "Frau" and "20 - 30 Jahre".
+  fn get_room(result: &NodeDataRef<ElementData>) -> RoomDetails {
+    // the size of the flat includes the room that is offered
+    let flatmates = Self::get_optional_text(result, ".ang_spalte_wg")
+      .and_then(|size| Self::parse_quantity(&size).value())
+      .map(|size| (size as u32).saturating_sub(1));
+    let gender = Self::get_optional_text(result, ".ang_spalte_gesucht").and_then(|gender| {
+      match gender.to_lowercase().as_str() {
+        "frau" | "frauen" => Some(Gender::Female),
+        "mann" | "männer" => Some(Gender::Male),
+        "egal" => Some(Gender::Any),
+        _ => None,
+      }
+    });
+    let age = Self::get_optional_text(result, ".ang_spalte_alter")
+      .map(|age| Self::parse_quantity(&age))
+      .unwrap_or_default();
+    RoomDetails {
+      flatmates,
+      gender,
+      age_min: age.lower.map(|age| age as u32),
+      age_max: age.upper.map(|age| age as u32),
+    }
+  }
+}
+
 impl Crawler for WGGesucht {
   fn metadata(&self) -> Metadata {
     Metadata {
       name: String::from("wggesucht"),
-      encoding: Encoding::Utf8,
+      encoding: encoding_rs::UTF_8,
     }
   }
 
-  fn selector(&self) -> &'static str {
-    "tr[adid^=wohnungen]"
+  fn selector(&self) -> &str {
+    "tr[adid^=wohnungen], tr[adid^=wg-zimmer]"
   }
 
-  fn transform_result(&self, result: NodeDataRef<ElementData>) -> Result<PropertyData, Error> {
-    let only_limited = Self::get_text(&result, ".ang_spalte_freibis")?.trim().len() > 0;
-    if only_limited {
-      Err(Error {
-        message: "Flat is only available for a limited time.".to_owned(),
-      })
+  fn next_page(&self, _document: &NodeRef, url: &Url, page: u32) -> Option<String> {
+    // result pages are numbered from zero within the last path segment,
+    // eg. /wohnungen-in-Muenchen.90.2.1.0.html
+    lazy_static! {
+      static ref PAGE_REGEX: Regex = Regex::new(r"\.\d+\.html$").unwrap();
+    }
+    if PAGE_REGEX.is_match(url.path()) {
+      let path = PAGE_REGEX
+        .replace(url.path(), format!(".{}.html", page).as_str())
+        .into_owned();
+      let mut next = url.clone();
+      next.set_path(&path);
+      Some(next.to_string())
     } else {
-      let rent = Self::get_text(&result, ".ang_spalte_miete")?;
-      let squaremeters = Self::get_text(&result, ".ang_spalte_groesse")?;
-      let rooms = Self::get_text(&result, ".ang_spalte_zimmer")?;
-      let title = "Wohnung auf WG Gesucht".to_owned();
-      let address = "München, ".to_owned()
-        + Self::get_text(&result, ".ang_spalte_stadt")?
-          .replace("\n", "")
-          .trim();
-      let externalid = Self::get_attr(&result, None, "adid")?;
-      Ok(PropertyData {
-        price: Self::parse_number(rent)?,
-        squaremeters: Self::parse_number(squaremeters)?,
-        plot_squaremeters: None,
-        address,
-        title,
-        rooms: Self::parse_number(rooms)?,
-        url: format!("https://www.wg-gesucht.de/{}", &externalid),
-        externalid,
-        property_type: PropertyType::Flat,
-        contract_type: ContractType::Rent,
-        tags: vec![],
-      })
+      None
+    }
+  }
+
+  fn transform_result(
+    &self,
+    context: &Context,
+    result: NodeDataRef<ElementData>,
+  ) -> Result<PropertyData, Error> {
+    let externalid = Self::get_attr(&result, None, "adid")?;
+    // rooms of shared flats are listed in their own section, eg. /wg-zimmer-in-Muenchen.90.0.1.0.html
+    let is_room = externalid.starts_with("wg-zimmer");
+    // offers that are only available for a limited time are sublets,
+    // flats are only of interest to watchers looking for one
+    let available_until = Self::get_date(&result, ".ang_spalte_freibis");
+    let contract_type = match available_until {
+      Some(_) => ContractType::Temporary,
+      None => context.config.contract_type.clone(),
+    };
+    if contract_type == ContractType::Temporary
+      && context.config.contract_type != ContractType::Temporary
+      && !is_room
+    {
+      return Err(Error::Skipped(
+        "Flat is only available for a limited time.".to_owned(),
+      ));
     }
+    // the rent on wg-gesucht includes all service charges
+    let rent = Self::get_text(&result, ".ang_spalte_miete")?;
+    let squaremeters = Self::get_text(&result, ".ang_spalte_groesse")?;
+    let (rooms, title, property_type, room) = if is_room {
+      (
+        1.0,
+        "WG-Zimmer auf WG Gesucht".to_owned(),
+        PropertyType::Room,
+        Some(Self::get_room(&result)),
+      )
+    } else {
+      (
+        Self::parse_number(Self::get_text(&result, ".ang_spalte_zimmer")?)?,
+        "Wohnung auf WG Gesucht".to_owned(),
+        context.config.property_type.clone(),
+        None,
+      )
+    };
+    // results only name the district of the watched city
+    let address = format!(
+      "{}, {}",
+      context.config.city,
+      Self::get_text(&result, ".ang_spalte_stadt")?
+        .replace("\n", "")
+        .trim()
+    );
+    Ok(PropertyData {
+      cold_rent: None,
+      warm_rent: Some(Self::parse_number(rent)?),
+      service_charges: None,
+      deposit: None,
+      purchase_price: None,
+      currency: Currency::Eur,
+      squaremeters: Self::parse_number(squaremeters)?,
+      plot_squaremeters: None,
+      address,
+      title,
+      rooms,
+      url: context.absolute_url(&externalid)?,
+      externalid,
+      property_type,
+      contract_type,
+      tags: vec![],
+      location: None,
+      available_from: Self::get_date(&result, ".ang_spalte_freiab"),
+      available_until,
+      room,
+      details: None,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::WGGesucht;
+  use crate::crawlers::fixtures::assert_snapshot;
+  use crate::crawlers::Crawler;
+  use chrono::NaiveDate;
+
+  #[test]
+  fn transform_recorded_results() {
+    assert_snapshot(
+      &WGGesucht {},
+      "wggesucht",
+      "https://www.wg-gesucht.de/wohnungen-in-Muenchen.90.2.1.0.html",
+    );
+  }
+
+  #[test]
+  fn parse_availability_dates() {
+    assert_eq!(
+      WGGesucht::parse_date("31.03.2021"),
+      Some(NaiveDate::from_ymd(2021, 3, 31))
+    );
+    assert_eq!(
+      WGGesucht::parse_date("ab 1.4.21"),
+      Some(NaiveDate::from_ymd(2021, 4, 1))
+    );
+    assert_eq!(
+      WGGesucht::parse_date("2021-04-01"),
+      Some(NaiveDate::from_ymd(2021, 4, 1))
+    );
+    assert_eq!(WGGesucht::parse_date("sofort"), None);
+  }
+
+  #[test]
+  fn transform_recorded_rooms() {
+    assert_snapshot(
+      &WGGesucht {},
+      "wggesucht-rooms",
+      "https://www.wg-gesucht.de/wg-zimmer-in-Muenchen.90.0.1.0.html",
+    );
   }
 }
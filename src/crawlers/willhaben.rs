@@ -0,0 +1,102 @@
+extern crate kuchiki;
+extern crate std;
+
+use super::crawler::with_query_param;
+use super::{Context, Crawler, Error};
+use crate::crawlers::Metadata;
+use crate::models::{Currency, PropertyData};
+use kuchiki::{ElementData, NodeDataRef, NodeRef};
+use url::Url;
+
+pub struct Willhaben {}
+
+impl Crawler for Willhaben {
+  fn metadata(&self) -> Metadata {
+    Metadata {
+      name: String::from("willhaben"),
+      encoding: encoding_rs::UTF_8,
+    }
+  }
+
+  fn selector(&self) -> &str {
+    "a[id^=search-result-entry-header-]"
+  }
+
+  fn next_page(&self, _document: &NodeRef, url: &Url, page: u32) -> Option<String> {
+    Some(with_query_param(url, "page", &(page + 1).to_string()))
+  }
+
+  fn transform_result(
+    &self,
+    context: &Context,
+    result: NodeDataRef<ElementData>,
+  ) -> Result<PropertyData, Error> {
+    let externalid = Self::get_attr(&result, None, "id")?
+      .trim_start_matches("search-result-entry-header-")
+      .to_string();
+    let link = Self::get_attr(&result, None, "href")?;
+    let title = Self::get_text(&result, "h3")?.trim().to_string();
+    let address = Self::get_attr(&result, Some("span[aria-label]"), "aria-label")?
+      .trim()
+      .to_string();
+    let price = Self::get_text(&result, "[data-testid^=search-result-entry-price-]")?;
+
+    // eg. "68 m²", "2 Zimmer", "Grundfläche 450 m²" or features like "Terrasse"
+    let facts = Self::get_texts(
+      &result,
+      "[data-testid^=search-result-entry-teaser-attributes-] span",
+    )?;
+    let find_fact = |matches: &dyn Fn(&str) -> bool| {
+      facts
+        .iter()
+        .find(|fact| matches(fact))
+        .map(|fact| fact.to_owned())
+    };
+    let squaremeters = find_fact(&|fact| fact.contains("m²") && !fact.contains("Grund"))
+      .ok_or_else(|| Error::Incomplete(String::from("no living space")))?;
+    let rooms = find_fact(&|fact| fact.contains("Zimmer"))
+      .ok_or_else(|| Error::Incomplete(String::from("no rooms")))?;
+    let plot_squaremeters =
+      find_fact(&|fact| fact.contains("Grund")).and_then(|fact| Self::parse_number(fact).ok());
+
+    Ok(PropertyData {
+      cold_rent: None,
+      // austrian portals state the rent including service charges (Gesamtmiete)
+      warm_rent: Some(Self::parse_number(price)?),
+      service_charges: None,
+      deposit: None,
+      purchase_price: None,
+      currency: Currency::Eur,
+      squaremeters: Self::parse_number(squaremeters)?,
+      plot_squaremeters,
+      address,
+      title,
+      rooms: Self::parse_number(rooms)?,
+      url: context.absolute_url(&link)?,
+      externalid,
+      property_type: context.config.property_type.clone(),
+      contract_type: context.config.contract_type.clone(),
+      tags: vec![],
+      location: None,
+      available_from: None,
+      available_until: None,
+      room: None,
+      details: None,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Willhaben;
+  use crate::crawlers::fixtures::assert_snapshot;
+
+  #[test]
+  fn transform_recorded_results() {
+    assert_snapshot(
+      &Willhaben {},
+      "willhaben",
+      "https://www.willhaben.at/iad/immobilien/mietwohnungen/wien",
+    );
+  }
+}
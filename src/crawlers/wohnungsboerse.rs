@@ -2,76 +2,88 @@ extern crate kuchiki;
 extern crate reqwest;
 extern crate std;
 
-use super::{Crawler, Error};
+use super::{schema, Context, Crawler, Error};
+use crate::crawlers::quantity::Locale;
 use crate::crawlers::Metadata;
-use crate::models::Encoding;
-use crate::models::{ContractType, PropertyData, PropertyType};
+use crate::models::{Currency, PropertyData};
 use kuchiki::{ElementData, NodeDataRef};
 
-impl From<()> for Error {
-  fn from(_: ()) -> Self {
-    Error {
-      message: "".to_owned(),
-    }
-  }
-}
-
 pub struct Wohnungsboerse {}
 
 impl Crawler for Wohnungsboerse {
   fn metadata(&self) -> Metadata {
     Metadata {
       name: String::from("wohnungsboerse"),
-      encoding: Encoding::Utf8,
+      encoding: encoding_rs::UTF_8,
     }
   }
 
-  fn selector(&self) -> &'static str {
+  fn selector(&self) -> &str {
     ".search_result_entry[class*='estate_']"
   }
 
-  fn transform_result(&self, result: NodeDataRef<ElementData>) -> Result<PropertyData, Error> {
+  fn transform_result(
+    &self,
+    context: &Context,
+    result: NodeDataRef<ElementData>,
+  ) -> Result<PropertyData, Error> {
     let title = Self::get_text(&result, ".search_result_entry-headline")?
       .trim()
       .to_string();
     let address = Self::get_text(&result, ".search_result_entry-subheadline")?
       .trim()
       .to_string();
-    let price = Self::get_attr(
-      &result,
-      Some("div[itemprop^=priceSpecification] meta[itemprop^=price]"),
-      "content",
-    )?;
-    let squaremeters = Self::get_attr(
-      &result,
-      Some("div[itemprop^=floorSize] meta[itemprop^=value]"),
-      "content",
-    )?;
-    let rooms = Self::get_attr(
-      &result,
-      Some("div[itemprop^=numberOfRooms] meta[itemprop^=value]"),
-      "content",
-    )?;
+    // every result is an offer described by structured data
+    let listing = schema::extract(result.as_node(), Locale::De)
+      .into_iter()
+      .next()
+      .ok_or_else(|| Error::Incomplete(String::from("no offer")))?;
+    let required = |value: Option<f32>, name: &str| {
+      value.ok_or_else(|| Error::Incomplete(format!("offer has no {}", name)))
+    };
     let link = Self::get_attr(&result, Some(".search_result_entry-headline a"), "href")?;
     let externalid_opt = link.rsplit("/").next();
 
     match externalid_opt {
       Some(externalid) => Ok(PropertyData {
-        price: Self::parse_number(price)?,
-        squaremeters: Self::parse_number(squaremeters)?,
+        cold_rent: Some(required(listing.price, "price")?),
+        warm_rent: None,
+        service_charges: None,
+        deposit: None,
+        purchase_price: None,
+        currency: Currency::Eur,
+        squaremeters: required(listing.floor_size, "floor size")?,
         plot_squaremeters: None,
         address,
         title,
-        rooms: Self::parse_number(rooms)?,
+        rooms: required(listing.rooms, "number of rooms")?,
         url: format!("https://www.wohnungsboerse.net/immodetail/{}", &externalid),
         externalid: externalid.to_string(),
-        property_type: PropertyType::Flat,
-        contract_type: ContractType::Rent,
+        property_type: context.config.property_type.clone(),
+        contract_type: context.config.contract_type.clone(),
         tags: vec![],
+        location: listing.location,
+        available_from: None,
+        available_until: None,
+        room: None,
+        details: None,
       }),
-      None => Err(Error {
-        message: "Could not find an external id".to_string(),
-      }),
+      None => Err(Error::Incomplete(String::from("no external id"))),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::Wohnungsboerse;
+  use crate::crawlers::fixtures::assert_snapshot;
+
+  #[test]
+  fn transform_recorded_results() {
+    assert_snapshot(
+      &Wohnungsboerse {},
+      "wohnungsboerse",
+      "https://www.wohnungsboerse.net/Muenchen/mieten/wohnungen",
+    );
+  }
+}
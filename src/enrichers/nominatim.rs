@@ -1,10 +1,11 @@
 use crate::enrichers::{Enricher, EnricherError};
-use crate::models::Property;
+use crate::models::{Property, PropertyData};
 use crate::ApplicationConfig;
 use async_trait::async_trait;
 use reqwest::header::HeaderValue;
 use reqwest::header::USER_AGENT;
 use std::collections::HashMap;
+use tokio::sync::Mutex;
 
 extern crate reqwest;
 extern crate serde;
@@ -71,7 +72,18 @@ impl From<ParseFloatError> for Error {
   }
 }
 
-pub struct Nominatim {}
+pub struct Nominatim {
+  /// held while geocoding, so properties enriched at the same time do not exceed one request per second
+  pub requests: Mutex<()>,
+}
+
+impl Nominatim {
+  pub fn new() -> Self {
+    Nominatim {
+      requests: Mutex::new(()),
+    }
+  }
+}
 
 #[async_trait]
 impl Enricher for Nominatim {
@@ -89,28 +101,48 @@ impl Enricher for Nominatim {
     property: &Property,
   ) -> Result<HashMap<String, String>, EnricherError> {
     match &property.data {
-      Some(data) => match geocode(app_config, &data.address).await {
-        Ok(geocode_result) => {
-          let mut enrichments = HashMap::new();
-          enrichments.insert(
-            String::from("latitude"),
-            geocode_result.coord.latitude.to_string(),
-          );
-          enrichments.insert(
-            String::from("longitude"),
-            geocode_result.coord.longitude.to_string(),
-          );
-          enrichments.insert(
-            String::from("uncertainty"),
-            geocode_result.uncertainty.to_string(),
-          );
-          Ok(enrichments)
-        }
-        Err(e) => {
-          println!("error during geocoding: {:?}", e);
-          Err(EnricherError { message: e.message })
+      // the portal already published the coordinates of the property
+      Some(PropertyData {
+        location: Some(location),
+        ..
+      }) => {
+        let mut enrichments = HashMap::new();
+        enrichments.insert(String::from("latitude"), location.latitude.to_string());
+        enrichments.insert(String::from("longitude"), location.longitude.to_string());
+        enrichments.insert(
+          String::from("uncertainty"),
+          location.uncertainty.to_string(),
+        );
+        Ok(enrichments)
+      }
+      Some(data) => {
+        let result = {
+          let _request = self.requests.lock().await;
+          geocode(app_config, &data.address).await
+        };
+        match result {
+          Ok(geocode_result) => {
+            let mut enrichments = HashMap::new();
+            enrichments.insert(
+              String::from("latitude"),
+              geocode_result.coord.latitude.to_string(),
+            );
+            enrichments.insert(
+              String::from("longitude"),
+              geocode_result.coord.longitude.to_string(),
+            );
+            enrichments.insert(
+              String::from("uncertainty"),
+              geocode_result.uncertainty.to_string(),
+            );
+            Ok(enrichments)
+          }
+          Err(e) => {
+            println!("error during geocoding: {:?}", e);
+            Err(EnricherError { message: e.message })
+          }
         }
-      },
+      }
       None => Err(EnricherError {
         message: String::from("No data."),
       }),
@@ -139,7 +171,7 @@ pub async fn geocode(
     .send()
     .await?;
   let one_second = std::time::Duration::from_secs(1);
-  std::thread::sleep(one_second);
+  tokio::time::delay_for(one_second).await;
 
   let result: Vec<ApiResult> = response.json().await?;
 
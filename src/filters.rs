@@ -8,7 +8,10 @@ pub use crate::filters::csv::CSV;
 pub use crate::filters::dynamodb::DynamoDbFilter;
 pub use crate::filters::filter::{Filter, FilterError};
 
+use crate::models::Property;
 use crate::ApplicationConfig;
+use std::collections::HashSet;
+use tokio::sync::Mutex;
 
 pub fn get_filters(app_config: &ApplicationConfig) -> Vec<Box<dyn Filter>> {
   let filters: Vec<Box<dyn Filter>> = vec![
@@ -30,3 +33,25 @@ pub fn get_filters(app_config: &ApplicationConfig) -> Vec<Box<dyn Filter>> {
     .map(|opt_filter| opt_filter.unwrap())
     .collect()
 }
+
+/// Checks whether all of the given properties have already been seen by any of the filters.
+pub async fn all_known(
+  app_config: &ApplicationConfig,
+  filters: &Mutex<Vec<Box<dyn Filter>>>,
+  properties: &Vec<Property>,
+) -> bool {
+  let mut known: HashSet<String> = HashSet::new();
+  for filter in filters.lock().await.iter_mut() {
+    match filter.known(app_config, properties).await {
+      Ok(ids) => known.extend(ids),
+      Err(e) => eprintln!(
+        "Error while looking up known properties in filter {}: {}",
+        &filter.name(),
+        e.message
+      ),
+    }
+  }
+  properties
+    .iter()
+    .all(|property| known.contains(&property.id()))
+}
@@ -1,8 +1,9 @@
-use crate::configuration::CriteriaConfig;
+use crate::configuration::{CriteriaConfig, PriceLimits};
 use crate::filters::{Filter, FilterError};
-use crate::models::Property;
+use crate::models::{Currency, Gender, Property, PropertyType};
 use crate::ApplicationConfig;
 use async_trait::async_trait;
+use chrono::{Datelike, Local, NaiveDate};
 
 pub struct CriteriaFilter {}
 
@@ -15,11 +16,16 @@ impl Filter for CriteriaFilter {
   fn init(&mut self, app_config: &ApplicationConfig) -> Result<(), String> {
     print!("Will filter for properties ");
 
-    match (app_config.criteria.price_min, app_config.criteria.price_max) {
-      (None, None) => print!("no matter the price"),
-      (Some(min), Some(max)) => print!("that cost at least {} and at most {}", min, max),
-      (None, Some(max)) => print!("that cost at most {}", max),
-      (Some(min), None) => print!("that cost at least {}", min),
+    let criteria = &app_config.criteria;
+    if let Some(limits) = price_limits(criteria, criteria.currency) {
+      print!("{}", describe_prices(&limits));
+    }
+    for limits in &criteria.prices {
+      print!(
+        " (if priced in {}, {})",
+        limits.currency.symbol(),
+        describe_prices(limits)
+      );
     }
 
     print!(" and ");
@@ -46,6 +52,32 @@ impl Filter for CriteriaFilter {
       (Some(min), None) => print!("that have at least {} rooms", min),
     }
 
+    let room = &app_config.criteria;
+    if room.gender.is_some() || room.age.is_some() || room.flatmates_max.is_some() {
+      print!(" and rooms in shared flats");
+      if let Some(gender) = room.gender {
+        let gender = match gender {
+          Gender::Female => "women",
+          Gender::Male => "men",
+          Gender::Any => "anyone",
+        };
+        print!(" that are offered to {}", gender);
+      }
+      if let Some(age) = room.age {
+        print!(" that accept an age of {}", age);
+      }
+      if let Some(max) = room.flatmates_max {
+        print!(" with at most {} flatmates", max);
+      }
+    }
+
+    if let Some(date) = app_config.criteria.available_before {
+      print!(" and that are available before {}", date);
+    }
+    if let Some(months) = app_config.criteria.min_months {
+      print!(" and that are available for at least {} months", months);
+    }
+
     println!(".");
 
     Ok(())
@@ -59,6 +91,16 @@ impl Filter for CriteriaFilter {
   ) -> Result<bool, FilterError> {
     Ok(self.evaluate(property, &app_config.criteria))
   }
+
+  /// Evaluates the criteria again, as some portals only give the availability
+  /// and the warm rent on the expose.
+  async fn filter_expose(
+    &mut self,
+    app_config: &ApplicationConfig,
+    property: &Property,
+  ) -> Result<bool, FilterError> {
+    Ok(self.evaluate(property, &app_config.criteria))
+  }
 }
 
 impl CriteriaFilter {
@@ -69,45 +111,151 @@ impl CriteriaFilter {
 
     let data = &property.data.as_ref().unwrap();
 
-    // evaluate price
-    if !criteria.price_min.map_or(true, |min| data.price >= min) {
-      return false;
+    // evaluate price, properties without a price might still match
+    // and prices in a currency without limits cannot be compared to them
+    if let Some(limits) = price_limits(criteria, data.currency) {
+      let price = data.price();
+      let total_rent = data.total_rent();
+      if !limits
+        .price_min
+        .map_or(true, |min| price.map_or(true, |price| price >= min))
+      {
+        return false;
+      }
+      if !limits
+        .price_max
+        .map_or(true, |max| price.map_or(true, |price| price <= max))
+      {
+        return false;
+      }
+      if !limits
+        .warm_rent_max
+        .map_or(true, |max| total_rent.map_or(true, |rent| rent <= max))
+      {
+        return false;
+      }
     }
-    if !criteria.price_max.map_or(true, |max| data.price <= max) {
-      return false;
+
+    // evaluate rooms and squaremeters, which are meant for whole flats and houses,
+    // while a room in a shared flat is always a single one of its own size
+    if data.property_type != PropertyType::Room {
+      if !criteria.rooms_min.map_or(true, |min| data.rooms >= min) {
+        return false;
+      }
+      if !criteria.rooms_max.map_or(true, |max| data.rooms <= max) {
+        return false;
+      }
+      if !criteria
+        .squaremeters_min
+        .map_or(true, |min| data.squaremeters >= min)
+      {
+        return false;
+      }
+      if !criteria
+        .squaremeters_max
+        .map_or(true, |max| data.squaremeters <= max)
+      {
+        return false;
+      }
     }
 
-    // evaluate rooms
-    if !criteria.rooms_min.map_or(true, |min| data.rooms >= min) {
-      return false;
+    // evaluate availability, properties without dates are available right away and without limit
+    if let (Some(before), Some(from)) = (criteria.available_before, data.available_from) {
+      if from > before {
+        return false;
+      }
     }
-    if !criteria.rooms_max.map_or(true, |max| data.rooms <= max) {
-      return false;
+    if let (Some(months), Some(until)) = (criteria.min_months, data.available_until) {
+      let from = data
+        .available_from
+        .unwrap_or_else(|| Local::today().naive_local());
+      // the last day of availability is included
+      if until.succ() < add_months(from, months) {
+        return false;
+      }
     }
 
-    // evaluate squaremeters
-    if !criteria
-      .squaremeters_min
-      .map_or(true, |min| data.squaremeters >= min)
-    {
-      return false;
-    }
-    if !criteria
-      .squaremeters_max
-      .map_or(true, |max| data.squaremeters <= max)
-    {
-      return false;
+    // evaluate the shared flat of rooms, preferences that are not given might still match
+    if let Some(ref room) = data.room {
+      let gender_matches = match (criteria.gender, room.gender) {
+        (Some(Gender::Any), _) | (_, Some(Gender::Any)) | (None, _) | (_, None) => true,
+        (Some(searching), Some(offered)) => searching == offered,
+      };
+      if !gender_matches {
+        return false;
+      }
+      if let Some(age) = criteria.age {
+        if !room.age_min.map_or(true, |min| age >= min)
+          || !room.age_max.map_or(true, |max| age <= max)
+        {
+          return false;
+        }
+      }
+      if !criteria.flatmates_max.map_or(true, |max| {
+        room.flatmates.map_or(true, |flatmates| flatmates <= max)
+      }) {
+        return false;
+      }
     }
 
     true
   }
 }
 
+/// The price limits for properties priced in the given currency, if there are any.
+fn price_limits(criteria: &CriteriaConfig, currency: Currency) -> Option<PriceLimits> {
+  if currency == criteria.currency {
+    Some(PriceLimits {
+      currency,
+      price_min: criteria.price_min,
+      price_max: criteria.price_max,
+      warm_rent_max: criteria.warm_rent_max,
+    })
+  } else {
+    criteria
+      .prices
+      .iter()
+      .find(|limits| limits.currency == currency)
+      .cloned()
+  }
+}
+
+fn describe_prices(limits: &PriceLimits) -> String {
+  let currency = limits.currency.symbol();
+  let mut description = match (limits.price_min, limits.price_max) {
+    (None, None) => String::from("no matter the price"),
+    (Some(min), Some(max)) => format!(
+      "that cost at least {} {} and at most {} {}",
+      min, currency, max, currency
+    ),
+    (None, Some(max)) => format!("that cost at most {} {}", max, currency),
+    (Some(min), None) => format!("that cost at least {} {}", min, currency),
+  };
+  if let Some(max) = limits.warm_rent_max {
+    description.push_str(&format!(
+      " with a warm rent of at most {} {}",
+      max, currency
+    ));
+  }
+  description
+}
+
+/// The same day the given number of months later, or the last day of that month if it is shorter.
+fn add_months(date: NaiveDate, months: u32) -> NaiveDate {
+  let month = date.month0() + months;
+  let year = date.year() + (month / 12) as i32;
+  (1..=date.day())
+    .rev()
+    .find_map(|day| NaiveDate::from_ymd_opt(year, month % 12 + 1, day))
+    .unwrap_or(date)
+}
+
 #[cfg(test)]
 mod tests {
-  use super::CriteriaConfig;
-  use super::CriteriaFilter;
-  use crate::models::Property;
+  use super::{add_months, CriteriaFilter};
+  use super::{CriteriaConfig, PriceLimits};
+  use crate::models::{Currency, Gender, Property, PropertyType, RoomDetails};
+  use chrono::NaiveDate;
 
   #[test]
   fn filter_pass() {
@@ -115,10 +263,18 @@ mod tests {
     let criteria = CriteriaConfig {
       price_min: None,
       price_max: Some(100.0),
+      warm_rent_max: None,
       squaremeters_min: Some(50.0),
       squaremeters_max: None,
       rooms_min: Some(2.0),
       rooms_max: None,
+      currency: Currency::Eur,
+      prices: vec![],
+      gender: None,
+      age: None,
+      flatmates_max: None,
+      available_before: None,
+      min_months: None,
     };
     let filter = CriteriaFilter {};
     let property = Property::dummy(100.0, 85.0, 2.0);
@@ -136,10 +292,18 @@ mod tests {
     let criteria = CriteriaConfig {
       price_min: None,
       price_max: Some(100.0),
+      warm_rent_max: None,
       squaremeters_min: Some(50.0),
       squaremeters_max: None,
       rooms_min: Some(2.0),
       rooms_max: None,
+      currency: Currency::Eur,
+      prices: vec![],
+      gender: None,
+      age: None,
+      flatmates_max: None,
+      available_before: None,
+      min_months: None,
     };
     let filter = CriteriaFilter {};
     let property = Property::dummy(200.0, 85.0, 2.0);
@@ -150,4 +314,266 @@ mod tests {
     // THEN
     assert_eq!(result, false);
   }
+
+  #[test]
+  fn filter_warm_rent() {
+    // GIVEN
+    let criteria = CriteriaConfig {
+      price_min: None,
+      price_max: Some(1000.0),
+      warm_rent_max: Some(1100.0),
+      squaremeters_min: None,
+      squaremeters_max: None,
+      rooms_min: None,
+      rooms_max: None,
+      currency: Currency::Eur,
+      prices: vec![],
+      gender: None,
+      age: None,
+      flatmates_max: None,
+      available_before: None,
+      min_months: None,
+    };
+    let filter = CriteriaFilter {};
+    let mut property = Property::dummy(950.0, 85.0, 2.0);
+    if let Some(ref mut data) = property.data {
+      data.service_charges = Some(200.0);
+    }
+
+    // WHEN
+    let result = filter.evaluate(&property, &criteria);
+
+    // THEN
+    assert_eq!(result, false);
+  }
+
+  #[test]
+  fn filter_ignores_price_in_other_currency() {
+    // GIVEN
+    let criteria = CriteriaConfig {
+      price_min: None,
+      price_max: Some(1000.0),
+      warm_rent_max: Some(1100.0),
+      squaremeters_min: Some(50.0),
+      squaremeters_max: None,
+      rooms_min: None,
+      rooms_max: None,
+      currency: Currency::Eur,
+      prices: vec![],
+      gender: None,
+      age: None,
+      flatmates_max: None,
+      available_before: None,
+      min_months: None,
+    };
+    let filter = CriteriaFilter {};
+    let mut property = Property::dummy(2450.0, 85.0, 2.0);
+    if let Some(ref mut data) = property.data {
+      data.currency = Currency::Chf;
+    }
+    let mut small_property = Property::dummy(2450.0, 45.0, 2.0);
+    if let Some(ref mut data) = small_property.data {
+      data.currency = Currency::Chf;
+    }
+
+    // WHEN
+    let result = filter.evaluate(&property, &criteria);
+    let small_result = filter.evaluate(&small_property, &criteria);
+
+    // THEN
+    assert_eq!(result, true);
+    assert_eq!(small_result, false);
+  }
+
+  #[test]
+  fn filter_price_limits_per_currency() {
+    // GIVEN
+    let criteria = CriteriaConfig {
+      price_min: None,
+      price_max: Some(1000.0),
+      warm_rent_max: None,
+      squaremeters_min: None,
+      squaremeters_max: None,
+      rooms_min: None,
+      rooms_max: None,
+      currency: Currency::Eur,
+      prices: vec![PriceLimits {
+        currency: Currency::Chf,
+        price_min: None,
+        price_max: Some(2000.0),
+        warm_rent_max: None,
+      }],
+      gender: None,
+      age: None,
+      flatmates_max: None,
+      available_before: None,
+      min_months: None,
+    };
+    let filter = CriteriaFilter {};
+    let priced = |price: f32, currency: Currency| {
+      let mut property = Property::dummy(price, 60.0, 2.0);
+      if let Some(ref mut data) = property.data {
+        data.currency = currency;
+      }
+      property
+    };
+
+    // THEN
+    assert_eq!(
+      filter.evaluate(&priced(950.0, Currency::Eur), &criteria),
+      true
+    );
+    assert_eq!(
+      filter.evaluate(&priced(1450.0, Currency::Eur), &criteria),
+      false
+    );
+    assert_eq!(
+      filter.evaluate(&priced(1450.0, Currency::Chf), &criteria),
+      true
+    );
+    assert_eq!(
+      filter.evaluate(&priced(2450.0, Currency::Chf), &criteria),
+      false
+    );
+  }
+
+  #[test]
+  fn filter_room_preferences() {
+    // GIVEN
+    let criteria = CriteriaConfig {
+      price_min: None,
+      price_max: None,
+      warm_rent_max: None,
+      squaremeters_min: None,
+      squaremeters_max: None,
+      rooms_min: None,
+      rooms_max: None,
+      currency: Currency::Eur,
+      prices: vec![],
+      gender: Some(Gender::Male),
+      age: Some(27),
+      flatmates_max: Some(3),
+      available_before: None,
+      min_months: None,
+    };
+    let filter = CriteriaFilter {};
+    let room = |gender, age_max, flatmates| {
+      let mut property = Property::dummy(550.0, 16.0, 1.0);
+      if let Some(ref mut data) = property.data {
+        data.property_type = PropertyType::Room;
+        data.room = Some(RoomDetails {
+          flatmates: Some(flatmates),
+          gender: Some(gender),
+          age_min: Some(20),
+          age_max: Some(age_max),
+        });
+      }
+      property
+    };
+
+    // THEN
+    assert_eq!(filter.evaluate(&room(Gender::Any, 30, 2), &criteria), true);
+    assert_eq!(filter.evaluate(&room(Gender::Male, 30, 3), &criteria), true);
+    assert_eq!(
+      filter.evaluate(&room(Gender::Female, 30, 2), &criteria),
+      false
+    );
+    assert_eq!(filter.evaluate(&room(Gender::Any, 25, 2), &criteria), false);
+    assert_eq!(filter.evaluate(&room(Gender::Any, 30, 4), &criteria), false);
+  }
+
+  #[test]
+  fn filter_rooms_regardless_of_flat_limits() {
+    // GIVEN
+    let criteria = CriteriaConfig {
+      price_min: None,
+      price_max: Some(1000.0),
+      warm_rent_max: None,
+      squaremeters_min: Some(50.0),
+      squaremeters_max: None,
+      rooms_min: Some(2.0),
+      rooms_max: None,
+      currency: Currency::Eur,
+      prices: vec![],
+      gender: None,
+      age: None,
+      flatmates_max: None,
+      available_before: None,
+      min_months: None,
+    };
+    let filter = CriteriaFilter {};
+    let mut room = Property::dummy(550.0, 16.0, 1.0);
+    if let Some(ref mut data) = room.data {
+      data.property_type = PropertyType::Room;
+    }
+    let small_flat = Property::dummy(550.0, 16.0, 1.0);
+
+    // WHEN
+    let room_result = filter.evaluate(&room, &criteria);
+    let flat_result = filter.evaluate(&small_flat, &criteria);
+
+    // THEN
+    assert!(room_result);
+    assert!(!flat_result);
+  }
+
+  #[test]
+  fn filter_availability() {
+    // GIVEN
+    let criteria = CriteriaConfig {
+      price_min: None,
+      price_max: None,
+      warm_rent_max: None,
+      squaremeters_min: None,
+      squaremeters_max: None,
+      rooms_min: None,
+      rooms_max: None,
+      currency: Currency::Eur,
+      prices: vec![],
+      gender: None,
+      age: None,
+      flatmates_max: None,
+      available_before: Some(NaiveDate::from_ymd(2026, 12, 1)),
+      min_months: Some(12),
+    };
+    let filter = CriteriaFilter {};
+    let available = |from: (i32, u32, u32), until: Option<(i32, u32, u32)>| {
+      let mut property = Property::dummy(950.0, 45.0, 2.0);
+      if let Some(ref mut data) = property.data {
+        data.available_from = Some(NaiveDate::from_ymd(from.0, from.1, from.2));
+        data.available_until = until.map(|(y, m, d)| NaiveDate::from_ymd(y, m, d));
+      }
+      property
+    };
+
+    // THEN
+    assert_eq!(
+      filter.evaluate(&available((2026, 11, 1), None), &criteria),
+      true
+    );
+    assert_eq!(
+      filter.evaluate(&available((2026, 12, 15), None), &criteria),
+      false
+    );
+    assert_eq!(
+      filter.evaluate(&available((2026, 11, 1), Some((2027, 10, 31))), &criteria),
+      true
+    );
+    assert_eq!(
+      filter.evaluate(&available((2026, 11, 1), Some((2027, 4, 30))), &criteria),
+      false
+    );
+  }
+
+  #[test]
+  fn add_months_to_end_of_month() {
+    assert_eq!(
+      add_months(NaiveDate::from_ymd(2026, 1, 31), 1),
+      NaiveDate::from_ymd(2026, 2, 28)
+    );
+    assert_eq!(
+      add_months(NaiveDate::from_ymd(2026, 11, 15), 14),
+      NaiveDate::from_ymd(2028, 1, 15)
+    );
+  }
 }
@@ -72,4 +72,21 @@ impl Filter for CSV {
       })
     }
   }
+
+  async fn known(
+    &mut self,
+    _: &ApplicationConfig,
+    properties: &Vec<Property>,
+  ) -> Result<Vec<String>, FilterError> {
+    Ok(
+      properties
+        .iter()
+        .filter(|property| match property.data {
+          Some(ref data) => self.ids.contains(&data.externalid),
+          None => false,
+        })
+        .map(|property| property.id())
+        .collect(),
+    )
+  }
 }
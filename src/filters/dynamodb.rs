@@ -6,13 +6,14 @@ use rusoto_core::Region;
 use rusoto_dynamodb::{
   AttributeValue, BatchGetItemInput, DynamoDb, DynamoDbClient, KeysAndAttributes,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use tokio::time::timeout;
 
 pub struct DynamoDbFilter {
   pub client: Option<DynamoDbClient>,
   pub existing: HashMap<String, bool>,
-  pub initialized: bool,
+  /// ids of the properties that have been looked up in the table
+  pub looked_up: HashSet<String>,
 }
 
 impl DynamoDbFilter {
@@ -20,7 +21,81 @@ impl DynamoDbFilter {
     DynamoDbFilter {
       client: None,
       existing: HashMap::new(),
-      initialized: false,
+      looked_up: HashSet::new(),
+    }
+  }
+
+  /// Looks up which of the given properties already exist in the table
+  /// and remembers their ids.
+  async fn load_existing(&mut self, app_config: &ApplicationConfig, properties: &Vec<Property>) {
+    for chunks in properties.chunks(100) {
+      let mut items: Vec<HashMap<String, AttributeValue>> = vec![];
+      for chunk in chunks {
+        let mut id = String::from(chunk.source.as_str());
+        id.push('-');
+        id.push_str(chunk.data.as_ref().unwrap().externalid.as_str());
+        let mut item = HashMap::new();
+        item.insert(
+          String::from("id"),
+          AttributeValue {
+            s: Some(id),
+            ..Default::default()
+          },
+        );
+        items.push(item)
+      }
+
+      let mut tables = HashMap::new();
+      tables.insert(
+        app_config.dynamodb.table_name.clone(),
+        KeysAndAttributes {
+          keys: items,
+          projection_expression: Some(String::from("id")),
+          ..Default::default()
+        },
+      );
+
+      let batch_get_input: BatchGetItemInput = BatchGetItemInput {
+        request_items: tables,
+        ..Default::default()
+      };
+
+      let mut retries = 0;
+      loop {
+        let batch_get_out_future = self
+          .client
+          .as_ref()
+          .unwrap()
+          .batch_get_item(batch_get_input.clone());
+        match timeout(std::time::Duration::from_millis(5000), batch_get_out_future).await {
+          Err(_) => {
+            retries = retries + 1;
+            eprintln!("(connection to dynamodb timed out #{})", retries);
+            if retries > 2 {
+              eprintln!("(giving up)");
+              break;
+            }
+          }
+          Ok(Ok(batch_get_output)) => match batch_get_output.responses {
+            Some(tables) => {
+              tables
+                .get(&app_config.dynamodb.table_name)
+                .unwrap()
+                .into_iter()
+                .map(|i| i.get("id").unwrap().s.as_ref().unwrap().clone())
+                .for_each(|el| {
+                  self.existing.insert(el.clone(), true);
+                });
+              break;
+            }
+            None => break,
+          },
+          Ok(Err(e)) => {
+            eprintln!("error: {}", e.to_string());
+            break;
+          }
+        }
+      }
     }
   }
 }
@@ -48,77 +123,18 @@ impl Filter for DynamoDbFilter {
     property: &Property,
     properties: &Vec<Property>,
   ) -> Result<bool, FilterError> {
-    if !self.initialized {
-      self.initialized = true;
-      for chunks in properties.chunks(100) {
-        let mut items: Vec<HashMap<String, AttributeValue>> = vec![];
-        for chunk in chunks {
-          let mut id = String::from(chunk.source.as_str());
-          id.push('-');
-          id.push_str(chunk.data.as_ref().unwrap().externalid.as_str());
-          let mut item = HashMap::new();
-          item.insert(
-            String::from("id"),
-            AttributeValue {
-              s: Some(id),
-              ..Default::default()
-            },
-          );
-          items.push(item)
-        }
-
-        let mut tables = HashMap::new();
-        tables.insert(
-          app_config.dynamodb.table_name.clone(),
-          KeysAndAttributes {
-            keys: items,
-            projection_expression: Some(String::from("id")),
-            ..Default::default()
-          },
-        );
-
-        let batch_get_input: BatchGetItemInput = BatchGetItemInput {
-          request_items: tables,
-          ..Default::default()
-        };
-
-        let mut retries = 0;
-        loop {
-          let batch_get_out_future = self
-            .client
-            .as_ref()
-            .unwrap()
-            .batch_get_item(batch_get_input.clone());
-          match timeout(std::time::Duration::from_millis(5000), batch_get_out_future).await {
-            Err(_) => {
-              retries = retries + 1;
-              eprintln!("(connection to dynamodb timed out #{})", retries);
-              if retries > 2 {
-                eprintln!("(giving up)");
-                break;
-              }
-            }
-            Ok(Ok(batch_get_output)) => match batch_get_output.responses {
-              Some(tables) => {
-                tables
-                  .get(&app_config.dynamodb.table_name)
-                  .unwrap()
-                  .into_iter()
-                  .map(|i| i.get("id").unwrap().s.as_ref().unwrap().clone())
-                  .for_each(|el| {
-                    self.existing.insert(el.clone(), true);
-                  });
-                break;
-              }
-              None => break,
-            },
-            Ok(Err(e)) => {
-              eprintln!("error: {}", e.to_string());
-              break;
-            }
-          }
-        }
-      }
+    // properties are looked up together with the other properties of their watcher
+    if !self.looked_up.contains(&property.id()) {
+      let pending: Vec<Property> = properties
+        .iter()
+        .filter(|other| !self.looked_up.contains(&other.id()))
+        .cloned()
+        .collect();
+      self.load_existing(app_config, &pending).await;
+      self
+        .looked_up
+        .extend(pending.iter().map(|property| property.id()));
+      self.looked_up.insert(property.id());
     }
 
     if property.data.is_some() {
@@ -136,4 +152,19 @@ impl Filter for DynamoDbFilter {
       })
     }
   }
+
+  async fn known(
+    &mut self,
+    app_config: &ApplicationConfig,
+    properties: &Vec<Property>,
+  ) -> Result<Vec<String>, FilterError> {
+    self.load_existing(app_config, properties).await;
+    Ok(
+      properties
+        .iter()
+        .map(|property| property.id())
+        .filter(|id| self.existing.contains_key(id))
+        .collect(),
+    )
+  }
 }
@@ -7,13 +7,35 @@ pub struct FilterError {
 }
 
 #[async_trait]
-pub trait Filter {
+pub trait Filter: Send {
   fn name(&self) -> String;
   fn init(&mut self, app_config: &ApplicationConfig) -> Result<(), String>;
+  /// Returns whether the property should be processed.
+  /// `properties` holds all properties found by the same watcher, including the given one.
   async fn filter(
     &mut self,
     app_config: &ApplicationConfig,
     property: &Property,
     properties: &Vec<Property>,
   ) -> Result<bool, FilterError>;
+
+  /// Returns whether a property, which passed `filter`, should still be processed once its expose
+  /// has been fetched, eg. because details like the availability are only found on the expose.
+  async fn filter_expose(
+    &mut self,
+    _app_config: &ApplicationConfig,
+    _property: &Property,
+  ) -> Result<bool, FilterError> {
+    Ok(true)
+  }
+
+  /// Returns the ids (see `Property::id`) of the given properties, that have been seen before.
+  /// Other than `filter`, this must not mark any of the properties as seen.
+  async fn known(
+    &mut self,
+    _app_config: &ApplicationConfig,
+    _properties: &Vec<Property>,
+  ) -> Result<Vec<String>, FilterError> {
+    Ok(vec![])
+  }
 }
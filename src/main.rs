@@ -1,28 +1,54 @@
+mod api;
 mod configuration;
 mod crawlers;
 mod enrichers;
 mod filters;
 mod models;
 mod observers;
+mod pipeline;
+mod schedule;
+mod shutdown;
 
-use crate::crawlers::Crawler;
+use crate::api::Control;
+use crate::crawlers::{CircuitBreaker, HealthMonitor, HttpClient};
 use crate::enrichers::get_enrichers;
 use crate::filters::get_filters;
 use crate::models::Property;
 use crate::observers::get_observers;
+use crate::pipeline::Pipeline;
+use crate::schedule::Timetable;
+use crate::shutdown::Shutdown;
 use configuration::ApplicationConfig;
 use crawlers::Config;
+use futures::channel::mpsc;
+use futures::stream::StreamExt;
 use lambda_runtime::{error::HandlerError, lambda, Context};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::io::prelude::*;
-use std::sync::Mutex;
-use std::sync::{Arc, Barrier};
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 fn run_lambda(e: ApplicationConfig, _: Context) -> Result<Vec<Property>, HandlerError> {
-  let properties = futures::executor::block_on(run(&e, true));
-  Ok(properties)
+  // every invocation starts from scratch, so unhealthy runs cannot be counted across invocations
+  if e.health.consecutive_runs > 1 {
+    eprintln!(
+      "health.consecutive_runs = {} has no effect within a lambda function, set it to 1 to be alerted about unhealthy runs.",
+      e.health.consecutive_runs
+    );
+  }
+  let mut health_monitor = HealthMonitor::new();
+  // blocked portals are alerted, but cannot be paused, as the next invocation does not know of the block
+  let mut breaker = CircuitBreaker::new();
+  futures::executor::block_on(run(
+    &e,
+    &e.watchers,
+    true,
+    &mut health_monitor,
+    &mut breaker,
+    &Shutdown::never(),
+    &Control::new(&e.api),
+  ))
+  .map_err(|e| HandlerError::from(e.as_str()))
 }
 
 #[tokio::main]
@@ -43,34 +69,109 @@ async fn main() {
   let app_config = configuration::read(config_path);
   println!("success.");
 
+  let shutdown = Shutdown::listen();
+  let control = Arc::new(Control::new(&app_config.api));
+  if app_config.api.enabled && !app_config.run_periodically {
+    // there is nothing to control, as the process exits after a single run
+    println!("control api is only started when running periodically.");
+  } else if app_config.api.enabled {
+    if let Err(e) = api::serve(&app_config.api, control.clone()) {
+      panic!("Control api could not be started: {}", e);
+    }
+  }
+
+  let mut exit_code = 0;
   let mut initial_run = app_config.initial_run;
+  let mut health_monitor = HealthMonitor::new();
+  let mut breaker = CircuitBreaker::new();
+  let mut timetable = Timetable::new(&app_config, chrono::Local::now().naive_local());
+  let mut triggered = false;
   loop {
-    run(&app_config, !initial_run).await;
-    if initial_run {
-      initial_run = false;
-      println!("initial run finished.");
+    let now = chrono::Local::now().naive_local();
+    let watchers = if triggered {
+      timetable.take_all(now)
+    } else {
+      timetable.take_due(now)
+    };
+    if watchers.is_empty() {
+      // eg. all watchers have been started within their quiet hours
+      control.finished(&[], timetable.next_run());
+    } else if control.is_paused() && !triggered {
+      println!("paused, skipping {} watcher(s).", watchers.len());
+      control.finished(&[], timetable.next_run());
     } else {
-      println!("run finished.");
+      control.started();
+      // only the last run decides about the exit code, earlier failures are retried on schedule
+      let properties = match run(
+        &app_config,
+        &watchers,
+        !initial_run,
+        &mut health_monitor,
+        &mut breaker,
+        &shutdown,
+        &control,
+      )
+      .await
+      {
+        Ok(properties) => {
+          exit_code = 0;
+          properties
+        }
+        Err(e) => {
+          eprintln!("run failed: {}.", e);
+          exit_code = 1;
+          vec![]
+        }
+      };
+      control.finished(&properties, timetable.next_run());
+      if initial_run {
+        initial_run = false;
+        println!("initial run finished.");
+      } else {
+        println!("run finished.");
+      }
     }
 
-    // pause until next run
-    if app_config.run_periodically {
-      println!("will now wait for {} seconds ...", app_config.interval);
-      std::thread::sleep(std::time::Duration::from_secs(app_config.interval));
-    } else {
-      break;
+    // pause until the next watcher is due, a run is triggered or the process is asked to stop
+    match timetable.next_run() {
+      Some(next_run) if app_config.run_periodically && !shutdown.is_requested() => {
+        println!("will now wait until {} ...", next_run.format("%H:%M:%S"));
+        let wait = next_run - chrono::Local::now().naive_local();
+        triggered = tokio::select! {
+          _ = tokio::time::delay_for(wait.to_std().unwrap_or_default()) => false,
+          _ = control.triggered() => true,
+          _ = shutdown.requested() => false,
+        };
+        if shutdown.is_requested() {
+          break;
+        }
+      }
+      _ => break,
     }
   }
+
+  println!("stopped.");
+  std::process::exit(exit_code);
 }
 
-async fn run(app_config: &ApplicationConfig, postprocess: bool) -> Vec<Property> {
+async fn run(
+  app_config: &ApplicationConfig,
+  watchers: &[Config],
+  postprocess: bool,
+  health_monitor: &mut HealthMonitor,
+  breaker: &mut CircuitBreaker,
+  shutdown: &Shutdown,
+  control: &Control,
+) -> Result<Vec<Property>, String> {
   let observers = get_observers(&app_config);
   let enrichers = get_enrichers(&app_config);
-  let mut filters = get_filters(&app_config);
+  let crawlers = crawlers::get_crawlers(app_config);
+  let filters = tokio::sync::Mutex::new(get_filters(&app_config));
+  let http = HttpClient::new(&app_config.http).expect("HTTP client could not be created");
   let run_started = Instant::now();
 
   let observer_names: Vec<String> = observers.iter().map(|o| o.name()).collect();
-  let filter_names: Vec<String> = filters.iter().map(|f| f.name()).collect();
+  let filter_names: Vec<String> = filters.lock().await.iter().map(|f| f.name()).collect();
   let enricher_names: Vec<String> = enrichers.iter().map(|e| e.name()).collect();
   println!();
   println!("starting run.");
@@ -82,210 +183,174 @@ async fn run(app_config: &ApplicationConfig, postprocess: bool) -> Vec<Property>
     println!("----- Running in TEST mode! -----");
   }
 
-  let thread_count = app_config.thread_count as usize;
-  let barrier = Arc::new(Barrier::new(thread_count + 1));
-
   let crawl_start = Instant::now();
-  let guarded_configs = Arc::new(Mutex::new(app_config.watchers.to_owned()));
+  let run_time = chrono::Local::now();
+  let active_watchers: Vec<Config> = watchers
+    .iter()
+    .filter(
+      |watcher| match breaker.cooldown(&watcher.crawler, run_time) {
+        Some(until) => {
+          println!(
+            "skipping {} watcher {}, the portal is paused until {}.",
+            watcher.crawler,
+            watcher.city,
+            until.format("%H:%M")
+          );
+          false
+        }
+        None => true,
+      },
+    )
+    .cloned()
+    .collect();
 
-  // process all crawlers
-  let mut thread_handles: Vec<_> = vec![];
-  for i in 0..thread_count {
-    let inner_guarded_configs = guarded_configs.clone();
-    let inner_barrier = barrier.clone();
-    let cap_conf = app_config.clone();
-    let handle = tokio::spawn(async move {
-      let properties = run_thread(thread_count, inner_guarded_configs, i, &cap_conf).await;
-      inner_barrier.wait();
-      properties
-    });
-    &mut thread_handles.push(handle);
-  }
+  // watchers pass on their results as soon as they finished,
+  // so their properties are processed while other watchers are still crawling
+  let (sender, receiver) = mpsc::unbounded();
+  let crawling = async {
+    crawlers::crawl(
+      app_config,
+      active_watchers,
+      &http,
+      &filters,
+      shutdown,
+      sender,
+    )
+    .await;
+    crawl_start.elapsed()
+  };
 
-  // wait for all threads to finish
-  barrier.wait();
+  let mut properties: Vec<Property> = vec![];
+  let mut alerts: Vec<String> = vec![];
+  let mut errors: HashMap<&str, usize> = HashMap::new();
+  let mut blocked: HashMap<String, String> = HashMap::new();
+  let mut succeeded: HashSet<String> = HashSet::new();
+  let batches = receiver.map(|(watcher, health, watcher_properties)| {
+    println!(
+      "finished {} watcher {}: {}.",
+      watcher.crawler,
+      watcher.city,
+      health.describe()
+    );
+    for (kind, count) in &health.errors {
+      *errors.entry(kind).or_insert(0) += count;
+    }
+    if let Some(ref reason) = health.blocked {
+      blocked.insert(watcher.crawler.clone(), reason.clone());
+    } else if health.error.is_none() {
+      succeeded.insert(watcher.crawler.clone());
+    }
+    control.record(&watcher, &health);
+    if let Some(alert) = health_monitor.record(&app_config.health, &watcher, &health) {
+      alerts.push(alert);
+    }
+    properties.extend(watcher_properties.iter().cloned());
+    (watcher, watcher_properties)
+  });
 
-  // collect results
-  let thread_results = futures::future::join_all(thread_handles)
-    .await
-    .into_iter()
-    .map(|result| async {
-      match result {
-        Ok(thread_properties) => thread_properties,
-        Err(_) => vec![],
-      }
-    });
-  let properties: Vec<_> = futures::future::join_all(thread_results)
-    .await
-    .into_iter()
-    .flatten()
-    .collect();
+  let pipeline = Pipeline {
+    app_config,
+    crawlers: &crawlers,
+    http: &http,
+    filters: &filters,
+    enrichers: &enrichers,
+    observers: &observers,
+  };
+  let processing = async {
+    if postprocess {
+      pipeline.process(batches).await
+    } else {
+      batches.for_each(|_| async {}).await;
+      vec![]
+    }
+  };
+  let completing = async { futures::join!(crawling, processing) };
+  // once the process has been asked to stop, the run is given a deadline to finish
+  let deadline = app_config.shutdown.deadline;
+  let aborting = async {
+    shutdown.requested().await;
+    tokio::time::delay_for(Duration::from_secs(deadline)).await;
+  };
+  let (crawl_duration, processed_properties, aborted) = tokio::select! {
+    (crawl_duration, processed_properties) = completing => (crawl_duration, processed_properties, false),
+    _ = aborting => {
+      eprintln!("run did not finish within {} seconds, aborting.", deadline);
+      (crawl_start.elapsed(), vec![], true)
+    }
+  };
+
+  println!("run summary:");
+  if !errors.is_empty() {
+    println!("> errors by kind: {:?}", errors);
+  }
+  for (portal, reason) in blocked.iter() {
+    alerts.push(breaker.record_block(&app_config.blocking, portal, reason.clone(), run_time));
+  }
+  for portal in succeeded
+    .iter()
+    .filter(|portal| !blocked.contains_key(*portal))
+  {
+    if let Some(alert) = breaker.record_success(portal) {
+      alerts.push(alert);
+    }
+  }
+  for pause in breaker.describe(chrono::Local::now()) {
+    println!("> {}.", pause);
+  }
+
+  for alert in &alerts {
+    eprintln!("{}", alert);
+    if !app_config.test && !aborted {
+      futures::future::join_all(observers.iter().map(|observer| async move {
+        if let Err(err) = observer.alert(app_config, alert).await {
+          eprintln!(
+            "Error while alerting observer {}: {}",
+            &observer.name(),
+            err.message
+          );
+        }
+      }))
+      .await;
+    }
+  }
 
-  let crawl_duration = crawl_start.elapsed();
   println!(
     "analyzed {} pages and found {} properties in {}.{:03} seconds.",
-    app_config.watchers.len(),
+    watchers.len(),
     properties.len(),
     crawl_duration.as_secs(),
     crawl_duration.subsec_millis()
   );
 
-  if !postprocess {
-    println!("will not process properties.");
-    properties
-  } else {
-    let processing_start = Instant::now();
-    let mut processed_properties = vec![];
-
-    let props = &properties.clone();
-    for mut property in properties {
-      let property_ref = &property;
-      println!("processing property {}:", property_ref.id());
-
-      if futures::future::join_all(filters.iter_mut().map(|filter| async move {
-        print!("> running filter {}", &filter.name());
-        let _ = std::io::stdout().flush();
-        match filter.filter(app_config, property_ref, props).await {
-          Ok(result) => {
-            println!(" ({}).", result);
-            result
-          }
-          Err(err) => {
-            eprintln!("Error during filter: {}", err.message);
-            true
-          }
-        }
-      }))
-      .await
-      .into_iter()
-      .all(std::convert::identity)
-      {
-        let property_ref = &property;
-        futures::future::join_all(enrichers.iter().map(|enricher| async move {
-          println!("> running enricher {}.", &enricher.name());
-          match enricher.enrich(app_config, property_ref).await {
-            Ok(enrichments) => enrichments,
-            Err(err) => {
-              eprintln!(
-                "Error while running enricher {}: {}",
-                &enricher.name(),
-                err.message
-              );
-              HashMap::new()
-            }
-          }
-        }))
-        .await
-        .into_iter()
-        .flatten()
-        .for_each(|(k, v)| {
-          property.enrichments.insert(k, v);
-        });
-
-        let property_ref = &property;
-        futures::future::join_all(observers.iter().map(|observer| async move {
-          println!("> running observer {}.", &observer.name());
-          match observer.observation(app_config, property_ref).await {
-            Ok(_) => (),
-            Err(err) => eprintln!(
-              "Error while running observer {}: {}",
-              &observer.name(),
-              err.message
-            ),
-          }
-        }))
-        .await;
-
-        processed_properties.push(property.clone());
-      } else {
-        println!("> removed by filter.");
-      }
+  let mut flushed = true;
+  for observer in observers.iter() {
+    if let Err(err) = observer.flush(app_config).await {
+      eprintln!(
+        "Error while flushing observer {}: {}",
+        &observer.name(),
+        err.message
+      );
+      flushed = false;
     }
+  }
 
-    let processing_duration = processing_start.elapsed();
-    if processed_properties.len() > 0 {
-      print!(" ")
-    };
-    println!(
-      "completed in {}.{:03} seconds.",
-      processing_duration.as_secs(),
-      processing_duration.subsec_millis()
-    );
-
+  if aborted {
+    Err(String::from("run has been aborted"))
+  } else if !flushed {
+    Err(String::from("observers could not be flushed"))
+  } else if !postprocess {
+    println!("will not process properties.");
+    Ok(properties)
+  } else {
     let run_duration = run_started.elapsed();
     println!(
       "found a total of {} items in {}.{:03} seconds of which {} passed filters.",
-      props.len(),
+      properties.len(),
       run_duration.as_secs(),
       run_duration.subsec_millis(),
       processed_properties.len(),
     );
 
-    processed_properties
-  }
-}
-
-async fn run_thread(
-  thread_count: usize,
-  guarded_configs: Arc<Mutex<Vec<Config>>>,
-  thread_number: usize,
-  app_config: &ApplicationConfig,
-) -> Vec<Property> {
-  let crawlers: Vec<Box<dyn Crawler>> = crawlers::get_crawlers();
-  let config_opt: Vec<Config> = match guarded_configs.lock() {
-    Ok(mut guard) => {
-      let configs_per_thread = (guard.len() as f32 / thread_count as f32).ceil() as usize;
-      guard.drain(..configs_per_thread).collect()
-    }
-    Err(e) => {
-      eprintln!(
-        "Could not acquire lock on shared configurations: {}.",
-        e.to_string()
-      );
-      vec![]
-    }
-  };
-  let futures = config_opt
-    .into_iter()
-    .map(|config| process_config(&crawlers, &app_config, config.clone(), thread_number));
-  futures::future::join_all(futures)
-    .await
-    .into_iter()
-    .flatten()
-    .collect()
-}
-
-async fn process_config(
-  crawlers: &Vec<Box<dyn Crawler>>,
-  app_config: &ApplicationConfig,
-  crawl_config: Config,
-  thread_number: usize,
-) -> Vec<Property> {
-  let crawler = crawlers::get_crawler(&crawl_config.crawler, crawlers);
-  match crawler {
-    Ok(crawler) => {
-      println!(
-        "processing '{}' on thread {} ...",
-        crawler.metadata().name,
-        thread_number
-      );
-      let properties_result = crawlers::execute(&crawl_config, &crawler).await;
-      if properties_result.is_ok() {
-        let properties = properties_result.unwrap();
-        if app_config.test {
-          for propety in &properties {
-            println!("parsed property: {:?}", propety);
-          }
-        }
-        properties
-      } else {
-        eprintln!("error: {:?}", properties_result.err().unwrap().message);
-        vec![]
-      }
-    }
-    Err(e) => {
-      eprintln!("config could not be processed: {:?}", e.message);
-      vec![]
-    }
+    Ok(processed_properties)
   }
 }
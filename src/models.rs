@@ -1,9 +1,11 @@
-mod encodings;
 mod flat;
 
-pub use self::encodings::Encoding;
+pub use self::flat::ContractType;
+pub use self::flat::Currency;
+pub use self::flat::Gender;
+pub use self::flat::Location;
 pub use self::flat::Property;
 pub use self::flat::PropertyData;
-pub use self::flat::Location;
+pub use self::flat::PropertyDetails;
 pub use self::flat::PropertyType;
-pub use self::flat::ContractType;
+pub use self::flat::RoomDetails;
@@ -1,7 +0,0 @@
-use serde_derive::{Deserialize, Serialize};
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum Encoding {
-    Latin1,
-    Utf8,
-}
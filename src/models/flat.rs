@@ -7,6 +7,8 @@ use std::collections::HashMap;
 pub enum PropertyType {
   House,
   Flat,
+  /// a room within a shared flat (WG-Zimmer)
+  Room,
 }
 
 impl std::str::FromStr for PropertyType {
@@ -15,6 +17,7 @@ impl std::str::FromStr for PropertyType {
     match s {
       "house" => Ok(PropertyType::House),
       "flat" => Ok(PropertyType::Flat),
+      "room" => Ok(PropertyType::Room),
       _ => Err(()),
     }
   }
@@ -24,6 +27,8 @@ impl std::str::FromStr for PropertyType {
 pub enum ContractType {
   Rent,
   Buy,
+  /// rent for a limited time only, eg. a sublet (Zwischenmiete)
+  Temporary,
 }
 
 impl std::str::FromStr for ContractType {
@@ -32,12 +37,69 @@ impl std::str::FromStr for ContractType {
     match s {
       "rent" => Ok(ContractType::Rent),
       "buy" => Ok(ContractType::Buy),
+      "temporary" => Ok(ContractType::Temporary),
       _ => Err(()),
     }
   }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Who may move into a room, or who is looking for one.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Gender {
+  Female,
+  Male,
+  /// no preference
+  Any,
+}
+
+impl std::str::FromStr for Gender {
+  type Err = ();
+  fn from_str(s: &str) -> Result<Self, ()> {
+    match s {
+      "female" => Ok(Gender::Female),
+      "male" => Ok(Gender::Male),
+      "any" => Ok(Gender::Any),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Currency {
+  #[serde(rename = "EUR")]
+  Eur,
+  #[serde(rename = "CHF")]
+  Chf,
+}
+
+impl std::str::FromStr for Currency {
+  type Err = ();
+  fn from_str(s: &str) -> Result<Self, ()> {
+    match s {
+      "EUR" => Ok(Currency::Eur),
+      "CHF" => Ok(Currency::Chf),
+      _ => Err(()),
+    }
+  }
+}
+
+impl Currency {
+  /// How amounts in this currency are labeled in messages, eg. "1,250 €" or "2,450 CHF".
+  pub fn symbol(self) -> &'static str {
+    match self {
+      Currency::Eur => "€",
+      Currency::Chf => "CHF",
+    }
+  }
+}
+
+impl Default for Currency {
+  fn default() -> Self {
+    Currency::Eur
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Location {
   pub latitude: f32,
   pub longitude: f32,
@@ -57,7 +119,23 @@ pub struct Property {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PropertyData {
-  pub price: f32,
+  /// rent without any additional costs (Kaltmiete)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub cold_rent: Option<f32>,
+  /// rent including service charges (Warmmiete)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub warm_rent: Option<f32>,
+  /// monthly costs in addition to the cold rent (Nebenkosten)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub service_charges: Option<f32>,
+  /// security deposit (Kaution)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub deposit: Option<f32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub purchase_price: Option<f32>,
+  /// currency of all prices above
+  #[serde(default)]
+  pub currency: Currency,
   pub contract_type: ContractType,
   pub property_type: PropertyType,
   pub squaremeters: f32,
@@ -69,7 +147,97 @@ pub struct PropertyData {
   pub rooms: f32,
   #[serde(skip_serializing)]
   pub tags: Vec<String>,
+  /// coordinates published by the portal
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub location: Option<Location>,
+  /// first day the property can be moved into
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub available_from: Option<NaiveDate>,
+  /// last day the property is available, if it is only available for a limited time
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub available_until: Option<NaiveDate>,
+  /// the shared flat, if the property is a room
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub room: Option<RoomDetails>,
   pub url: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub details: Option<PropertyDetails>,
+}
+
+impl PropertyData {
+  /// The price that matters for the contract: the purchase price when buying
+  /// and the cold rent when renting, which is derived from the warm rent if necessary.
+  /// Without service charges, the cold rent of a warm rent is unknown, so there is no price.
+  pub fn price(&self) -> Option<f32> {
+    match self.contract_type {
+      ContractType::Buy => self.purchase_price,
+      ContractType::Rent | ContractType::Temporary => self.cold_rent.or_else(|| {
+        self
+          .warm_rent
+          .and_then(|warm_rent| Some(warm_rent - self.service_charges?))
+      }),
+    }
+  }
+
+  /// The total monthly rent, which is derived from the cold rent and service charges if necessary.
+  pub fn total_rent(&self) -> Option<f32> {
+    match (self.warm_rent, self.cold_rent, self.service_charges) {
+      (Some(warm_rent), _, _) => Some(warm_rent),
+      (None, Some(cold_rent), Some(service_charges)) => Some(cold_rent + service_charges),
+      _ => None,
+    }
+  }
+}
+
+/// Information about a room and the shared flat it belongs to.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoomDetails {
+  /// number of people already living in the flat
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub flatmates: Option<u32>,
+  /// who the flatmates are looking for
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub gender: Option<Gender>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub age_min: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub age_max: Option<u32>,
+}
+
+impl RoomDetails {
+  /// Who the flatmates are looking for in words, eg. "women aged 20 to 30".
+  pub fn wanted(&self) -> Option<String> {
+    let gender = match self.gender {
+      Some(Gender::Female) => "women",
+      Some(Gender::Male) => "men",
+      Some(Gender::Any) | None => "anyone",
+    };
+    let age = match (self.age_min, self.age_max) {
+      (Some(min), Some(max)) => format!(" aged {} to {}", min, max),
+      (Some(min), None) => format!(" aged {} or older", min),
+      (None, Some(max)) => format!(" aged up to {}", max),
+      (None, None) => String::new(),
+    };
+    match (self.gender, age.is_empty()) {
+      (None, true) => None,
+      _ => Some(format!("{}{}", gender, age)),
+    }
+  }
+}
+
+/// Information that is only available on the detail page (expose) of a property.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PropertyDetails {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub floor: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub year_of_construction: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub energy_certificate: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub heating_type: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub description: Option<String>,
 }
 
 impl PartialEq for Property {
@@ -112,7 +280,12 @@ impl Property {
       source: String::new(),
       city: String::new(),
       data: Some(PropertyData {
-        price,
+        cold_rent: Some(price),
+        warm_rent: None,
+        service_charges: None,
+        deposit: None,
+        purchase_price: None,
+        currency: Currency::Eur,
         squaremeters,
         address: String::new(),
         title: String::new(),
@@ -122,6 +295,11 @@ impl Property {
         contract_type: ContractType::Rent,
         property_type: PropertyType::Flat,
         tags: vec![],
+        location: None,
+        available_from: None,
+        available_until: None,
+        room: None,
+        details: None,
         url: String::new(),
       }),
     }
@@ -151,6 +329,7 @@ impl Property {
 #[cfg(test)]
 mod tests {
   use crate::models::ContractType;
+  use crate::models::Currency;
   use crate::models::Property;
   use crate::models::PropertyData;
   use crate::models::PropertyType;
@@ -174,7 +353,12 @@ mod tests {
       city: String::from("Munich"),
       date: 0,
       data: Some(PropertyData {
-        price: 100.,
+        cold_rent: Some(100.),
+        warm_rent: None,
+        service_charges: None,
+        deposit: None,
+        purchase_price: None,
+        currency: Currency::Eur,
         squaremeters: 100.,
         address: String::from("Some address"),
         title: String::from("This is some title"),
@@ -184,6 +368,11 @@ mod tests {
         contract_type: ContractType::Rent,
         property_type: PropertyType::Flat,
         tags: vec![],
+        location: None,
+        available_from: None,
+        available_until: None,
+        room: None,
+        details: None,
         url: String::from(""),
       }),
       enrichments: HashMap::new(),
@@ -193,7 +382,12 @@ mod tests {
       city: String::from("Munich"),
       date: 0,
       data: Some(PropertyData {
-        price: 100.,
+        cold_rent: Some(100.),
+        warm_rent: None,
+        service_charges: None,
+        deposit: None,
+        purchase_price: None,
+        currency: Currency::Eur,
         squaremeters: 100.,
         address: String::from("This is some other address"),
         title: String::from("This is some other title"),
@@ -203,6 +397,11 @@ mod tests {
         contract_type: ContractType::Rent,
         property_type: PropertyType::Flat,
         tags: vec![],
+        location: None,
+        available_from: None,
+        available_until: None,
+        room: None,
+        details: None,
         url: String::from(""),
       }),
       enrichments: HashMap::new(),
@@ -220,7 +419,12 @@ mod tests {
       city: String::from("Munich"),
       date: 0,
       data: Some(PropertyData {
-        price: 100.,
+        cold_rent: Some(100.),
+        warm_rent: None,
+        service_charges: None,
+        deposit: None,
+        purchase_price: None,
+        currency: Currency::Eur,
         squaremeters: 100.,
         address: String::from("Some address"),
         title: String::from("This is some title"),
@@ -230,6 +434,11 @@ mod tests {
         contract_type: ContractType::Rent,
         property_type: PropertyType::Flat,
         tags: vec![],
+        location: None,
+        available_from: None,
+        available_until: None,
+        room: None,
+        details: None,
         url: String::from(""),
       }),
       enrichments: HashMap::new(),
@@ -239,7 +448,12 @@ mod tests {
       city: String::from("Munich"),
       date: 0,
       data: Some(PropertyData {
-        price: 100.,
+        cold_rent: Some(100.),
+        warm_rent: None,
+        service_charges: None,
+        deposit: None,
+        purchase_price: None,
+        currency: Currency::Eur,
         squaremeters: 100.,
         address: String::from("Some address"),
         title: String::from("This is some title"),
@@ -249,6 +463,11 @@ mod tests {
         contract_type: ContractType::Rent,
         property_type: PropertyType::Flat,
         tags: vec![],
+        location: None,
+        available_from: None,
+        available_until: None,
+        room: None,
+        details: None,
         url: String::from(""),
       }),
       enrichments: HashMap::new(),
@@ -266,7 +485,12 @@ mod tests {
       city: String::from("Munich"),
       date: 0,
       data: Some(PropertyData {
-        price: 100.,
+        cold_rent: Some(100.),
+        warm_rent: None,
+        service_charges: None,
+        deposit: None,
+        purchase_price: None,
+        currency: Currency::Eur,
         squaremeters: 100.,
         address: String::from("Some address"),
         title: String::from("This is% some title!"),
@@ -276,6 +500,11 @@ mod tests {
         contract_type: ContractType::Rent,
         property_type: PropertyType::Flat,
         tags: vec![],
+        location: None,
+        available_from: None,
+        available_until: None,
+        room: None,
+        details: None,
         url: String::from(""),
       }),
       enrichments: HashMap::new(),
@@ -285,7 +514,12 @@ mod tests {
       city: String::from("Munich"),
       date: 0,
       data: Some(PropertyData {
-        price: 101.,
+        cold_rent: Some(101.),
+        warm_rent: None,
+        service_charges: None,
+        deposit: None,
+        purchase_price: None,
+        currency: Currency::Eur,
         squaremeters: 101.,
         address: String::from("Some other address"),
         title: String::from("This is some title"),
@@ -295,6 +529,11 @@ mod tests {
         contract_type: ContractType::Rent,
         property_type: PropertyType::Flat,
         tags: vec![],
+        location: None,
+        available_from: None,
+        available_until: None,
+        room: None,
+        details: None,
         url: String::from(""),
       }),
       enrichments: HashMap::new(),
@@ -322,7 +561,12 @@ mod tests {
       city: String::from("Munich"),
       date: 0,
       data: Some(PropertyData {
-        price: 100.,
+        cold_rent: Some(100.),
+        warm_rent: None,
+        service_charges: None,
+        deposit: None,
+        purchase_price: None,
+        currency: Currency::Eur,
         squaremeters: 100.,
         address: String::from("Some address"),
         title: String::from("This is% some title!"),
@@ -332,6 +576,11 @@ mod tests {
         contract_type: ContractType::Buy,
         property_type: PropertyType::House,
         tags: vec![],
+        location: None,
+        available_from: None,
+        available_until: None,
+        room: None,
+        details: None,
         url: String::from(""),
       }),
       enrichments: HashMap::new(),
@@ -341,7 +590,12 @@ mod tests {
       city: String::from("Munich"),
       date: 0,
       data: Some(PropertyData {
-        price: 101.,
+        cold_rent: Some(101.),
+        warm_rent: None,
+        service_charges: None,
+        deposit: None,
+        purchase_price: None,
+        currency: Currency::Eur,
         squaremeters: 101.,
         address: String::from("Some other address"),
         title: String::from("This is some other title"),
@@ -351,6 +605,11 @@ mod tests {
         contract_type: ContractType::Buy,
         property_type: PropertyType::House,
         tags: vec![],
+        location: None,
+        available_from: None,
+        available_until: None,
+        room: None,
+        details: None,
         url: String::from(""),
       }),
       enrichments: HashMap::new(),
@@ -359,4 +618,22 @@ mod tests {
     // THEN
     assert_ne!(flat_a, flat_b);
   }
+
+  #[test]
+  fn price_of_warm_rent() {
+    // GIVEN
+    let mut data = Property::dummy(0., 20., 1.).data.unwrap();
+    data.cold_rent = None;
+    data.warm_rent = Some(550.);
+
+    // WHEN
+    let without_service_charges = data.price();
+    data.service_charges = Some(120.);
+    let with_service_charges = data.price();
+
+    // THEN
+    assert_eq!(without_service_charges, None);
+    assert_eq!(with_service_charges, Some(430.));
+    assert_eq!(data.total_rent(), Some(550.));
+  }
 }
@@ -1,7 +1,8 @@
-use crate::models::Property;
+use crate::models::{Currency, Property};
 use crate::observers::{Observer, ObserverError};
 use crate::ApplicationConfig;
 use async_trait::async_trait;
+use chrono::NaiveDate;
 use serde_derive::{Deserialize, Serialize};
 use std::io::prelude::*;
 
@@ -13,7 +14,15 @@ struct CSVProperty {
   pub url: String,
   pub date: i64,
   pub city: String,
-  pub price: f32,
+  pub price: Option<f32>,
+  pub cold_rent: Option<f32>,
+  pub warm_rent: Option<f32>,
+  pub service_charges: Option<f32>,
+  pub deposit: Option<f32>,
+  pub purchase_price: Option<f32>,
+  pub currency: Currency,
+  pub available_from: Option<NaiveDate>,
+  pub available_until: Option<NaiveDate>,
   pub squaremeters: f32,
   pub plot_squaremeters: f32,
   pub address: String,
@@ -41,19 +50,6 @@ impl Observer for CSV {
     property: &Property,
   ) -> Result<(), ObserverError> {
     if property.data.is_some() {
-      let file = std::fs::OpenOptions::new()
-        .create(true)
-        .read(true)
-        .append(true)
-        .open(&app_config.csv.filename)
-        .expect(
-          format!(
-            "Could not open file {} for reading.",
-            &app_config.csv.filename
-          )
-          .as_str(),
-        );
-
       let property_data = property.data.as_ref().unwrap();
       let csv_property = CSVProperty {
         source: property.source.clone(),
@@ -62,7 +58,15 @@ impl Observer for CSV {
         url: property_data.url.clone(),
         date: property.date,
         city: property.city.clone(),
-        price: property_data.price,
+        price: property_data.price(),
+        cold_rent: property_data.cold_rent,
+        warm_rent: property_data.warm_rent,
+        service_charges: property_data.service_charges,
+        deposit: property_data.deposit,
+        purchase_price: property_data.purchase_price,
+        currency: property_data.currency,
+        available_from: property_data.available_from,
+        available_until: property_data.available_until,
         squaremeters: property_data.squaremeters,
         plot_squaremeters: property_data.plot_squaremeters.unwrap_or(0.0),
         address: property_data.address.clone(),
@@ -96,32 +100,118 @@ impl Observer for CSV {
       buf_reader.read_line(&mut data_row)?;
 
       // read first line of csv file
-      let mut file_reader = std::io::BufReader::new(file);
-      file_reader.seek(std::io::SeekFrom::Start(0))?;
+      let filename = &app_config.csv.filename;
       let mut first_line = String::new();
-      file_reader.read_line(&mut first_line)?;
-      let file = file_reader.into_inner();
-
-      let mut file_writer = std::io::BufWriter::new(file);
-      file_writer.seek(std::io::SeekFrom::End(0))?;
+      match std::fs::File::open(filename) {
+        Ok(file) => {
+          std::io::BufReader::new(file).read_line(&mut first_line)?;
+        }
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => (),
+        Err(e) => return Err(e.into()),
+      }
 
       // this is a new file
-      if first_line.len() == 0 {
-        file_writer.write(header_row.as_bytes())?;
+      let row = if first_line.is_empty() {
+        header_row + &data_row
 
       // file already present
-      } else if header_row != first_line {
-        eprintln!(
-          "CSV file {} already present, but columns are not compatible!",
-          app_config.csv.filename
-        );
-        eprintln!("Expected: '{}', but was: '{}'", &header_row, &first_line);
-        std::process::exit(1);
-      }
+      } else {
+        // the file was written by a previous version with other columns
+        if header_row != first_line {
+          migrate(filename, &header_row)?;
+        }
+        data_row
+      };
 
-      // add new row
-      file_writer.write(data_row.as_bytes())?;
+      let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(filename)
+        .expect(format!("Could not open file {} for writing.", filename).as_str());
+      // header and row are appended at once, so an interrupted run does not leave half a row
+      file.write_all(row.as_bytes())?;
     }
     Ok(())
   }
+
+  async fn flush(&self, app_config: &ApplicationConfig) -> Result<(), ObserverError> {
+    match std::fs::File::open(&app_config.csv.filename) {
+      Ok(file) => Ok(file.sync_all()?),
+      // nothing has been written yet
+      Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+      Err(e) => Err(e.into()),
+    }
+  }
+}
+
+/// Rewrites a file, whose columns differ from the current ones (eg. after an update), with the
+/// current columns. Values are kept by the name of their column, new columns remain empty and
+/// columns that no longer exist are dropped. The original file is kept as `<filename>.bak`.
+fn migrate(filename: &str, header_row: &str) -> Result<(), ObserverError> {
+  let columns = csv::Reader::from_reader(header_row.as_bytes())
+    .headers()?
+    .clone();
+  let mut reader = csv::Reader::from_path(filename)?;
+  let previous_columns = reader.headers()?.clone();
+  let migrated = format!("{}.migrated", filename);
+  let mut writer = csv::Writer::from_path(&migrated)?;
+  writer.write_record(&columns)?;
+  let mut rows = 0;
+  for record in reader.records() {
+    let record = record?;
+    writer.write_record(columns.iter().map(|column| {
+      previous_columns
+        .iter()
+        .position(|previous_column| previous_column == column)
+        .and_then(|index| record.get(index))
+        .unwrap_or("")
+    }))?;
+    rows += 1;
+  }
+  writer.flush()?;
+  std::fs::rename(filename, format!("{}.bak", filename))?;
+  std::fs::rename(&migrated, filename)?;
+  println!(
+    "migrated {} entries of csv {} to the current columns, the previous file has been kept as {}.bak.",
+    rows, filename, filename
+  );
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::migrate;
+
+  #[test]
+  fn migrate_keeps_values_by_column() {
+    // GIVEN
+    let filename = std::env::temp_dir().join(format!("properwatcher-{}.csv", std::process::id()));
+    let filename = filename.to_str().unwrap();
+    std::fs::write(
+      filename,
+      "source,source_id,price,rooms\nimmoscout,123,750,2\n",
+    )
+    .unwrap();
+
+    // WHEN
+    let result = migrate(
+      filename,
+      "source,source_id,cold_rent,price,rooms,currency\n",
+    );
+
+    // THEN
+    let migrated = std::fs::read_to_string(filename).unwrap();
+    let backup = std::fs::read_to_string(format!("{}.bak", filename)).unwrap();
+    std::fs::remove_file(filename).unwrap();
+    std::fs::remove_file(format!("{}.bak", filename)).unwrap();
+    assert!(result.is_ok());
+    assert_eq!(
+      migrated,
+      "source,source_id,cold_rent,price,rooms,currency\nimmoscout,123,,750,2,\n"
+    );
+    assert_eq!(
+      backup,
+      "source,source_id,price,rooms\nimmoscout,123,750,2\n"
+    );
+  }
 }
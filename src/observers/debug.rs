@@ -1,4 +1,4 @@
-use crate::models::{ContractType, Property};
+use crate::models::Property;
 use crate::observers::observer::{Observer, ObserverError};
 use crate::ApplicationConfig;
 use async_trait::async_trait;
@@ -30,9 +30,17 @@ impl Observer for DebugObserver {
         println!("id: {}", data.externalid);
         println!("title: {}", data.title);
         println!("address: {}", data.address);
-        match data.contract_type {
-          ContractType::Rent => println!("rent: {}", data.price),
-          ContractType::Buy => println!("price: {}", data.price),
+        let prices = [
+          ("cold rent", data.cold_rent),
+          ("warm rent", data.warm_rent),
+          ("service charges", data.service_charges),
+          ("deposit", data.deposit),
+          ("price", data.purchase_price),
+        ];
+        for (name, price) in prices.iter() {
+          if let Some(price) = price {
+            println!("{}: {} {}", name, price, data.currency.symbol());
+          }
         }
         if data.plot_squaremeters.is_some() {
           println!(
@@ -44,8 +52,20 @@ impl Observer for DebugObserver {
           println!("squaremeters: {}", data.squaremeters);
         }
         println!("rooms: {}", data.rooms);
+        if let Some(ref room) = data.room {
+          println!("room: {:?}", room);
+        }
+        if data.available_from.is_some() || data.available_until.is_some() {
+          println!(
+            "available: {:?} - {:?}",
+            data.available_from, data.available_until
+          );
+        }
         println!("tags: {:?}", data.tags);
         println!("url: {}", data.url);
+        if let Some(ref details) = data.details {
+          println!("details: {:?}", details);
+        }
       }
       None => (),
     }
@@ -55,4 +75,9 @@ impl Observer for DebugObserver {
 
     Ok(())
   }
+  async fn alert(&self, _: &ApplicationConfig, message: &str) -> Result<(), ObserverError> {
+    println!();
+    println!("Alert: {}", message);
+    Ok(())
+  }
 }
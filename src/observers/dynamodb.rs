@@ -1,7 +1,8 @@
-use crate::models::{ContractType, Property, PropertyType};
+use crate::models::{ContractType, Currency, Property, PropertyType};
 use crate::observers::{Observer, ObserverError};
 use crate::ApplicationConfig;
 use async_trait::async_trait;
+use chrono::NaiveDate;
 use rusoto_core::Region;
 use rusoto_dynamodb::{DynamoDb, DynamoDbClient, PutItemInput};
 use serde_derive::{Deserialize, Serialize};
@@ -35,9 +36,19 @@ struct DynamoDbEntry {
   pub can_be_bought: bool,
   pub is_flat: bool,
   pub is_house: bool,
+  pub is_room: bool,
+  pub is_temporary: bool,
   pub date: i64,
   pub city: String,
-  pub price: f32,
+  pub price: Option<f32>,
+  pub cold_rent: Option<f32>,
+  pub warm_rent: Option<f32>,
+  pub service_charges: Option<f32>,
+  pub deposit: Option<f32>,
+  pub purchase_price: Option<f32>,
+  pub currency: Currency,
+  pub available_from: Option<NaiveDate>,
+  pub available_until: Option<NaiveDate>,
   pub squaremeters: f32,
   pub plot_squaremeters: Option<f32>,
   pub address: String,
@@ -77,8 +88,18 @@ impl Observer for DynamoDbObserver {
         can_be_bought: property_data.contract_type == ContractType::Buy,
         is_flat: property_data.property_type == PropertyType::Flat,
         is_house: property_data.property_type == PropertyType::House,
+        is_room: property_data.property_type == PropertyType::Room,
+        is_temporary: property_data.contract_type == ContractType::Temporary,
         date: property.date,
-        price: property_data.price,
+        price: property_data.price(),
+        cold_rent: property_data.cold_rent,
+        warm_rent: property_data.warm_rent,
+        service_charges: property_data.service_charges,
+        deposit: property_data.deposit,
+        purchase_price: property_data.purchase_price,
+        currency: property_data.currency,
+        available_from: property_data.available_from,
+        available_until: property_data.available_until,
         city: property.city.clone(),
         squaremeters: property_data.squaremeters,
         plot_squaremeters: property_data.plot_squaremeters,
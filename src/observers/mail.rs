@@ -26,34 +26,48 @@ impl Observer for Mail {
   ) -> Result<(), ObserverError> {
     if property.data.is_some() {
       let message = build_message(property);
+      let subject = format!("Found new flat: {}", property.data.as_ref().unwrap().title);
+      send_mail(app_config, subject, message);
+    }
+    Ok(())
+  }
+
+  async fn alert(
+    &self,
+    app_config: &ApplicationConfig,
+    message: &str,
+  ) -> Result<(), ObserverError> {
+    send_mail(
+      app_config,
+      String::from("Watcher needs attention"),
+      message.replace("\n", "<br />"),
+    );
+    Ok(())
+  }
+}
 
-      let email = EmailBuilder::new()
-        .to(app_config.mail.username.to_owned())
-        .from(app_config.mail.username.to_owned())
-        .subject(format!(
-          "Found new flat: {}",
-          property.data.as_ref().unwrap().title
-        ))
-        .html(message)
-        .build();
+fn send_mail(app_config: &ApplicationConfig, subject: String, message: String) {
+  let email = EmailBuilder::new()
+    .to(app_config.mail.username.to_owned())
+    .from(app_config.mail.username.to_owned())
+    .subject(subject)
+    .html(message)
+    .build();
 
-      let creds = Credentials::new(
-        app_config.mail.username.to_owned(),
-        app_config.mail.password.to_owned(),
-      );
+  let creds = Credentials::new(
+    app_config.mail.username.to_owned(),
+    app_config.mail.password.to_owned(),
+  );
 
-      let mut mailer = SmtpClient::new_simple(app_config.mail.smtp_server.as_str())
-        .unwrap()
-        .credentials(creds)
-        .transport();
+  let mut mailer = SmtpClient::new_simple(app_config.mail.smtp_server.as_str())
+    .unwrap()
+    .credentials(creds)
+    .transport();
 
-      let result = mailer.send(email.unwrap().into());
+  let result = mailer.send(email.unwrap().into());
 
-      if !result.is_ok() {
-        println!("Could not send email: {:?}", result);
-      }
-    }
-    Ok(())
+  if !result.is_ok() {
+    println!("Could not send email: {:?}", result);
   }
 }
 
@@ -63,33 +77,88 @@ fn build_message(property: &Property) -> String {
   let property_type = match property_data.property_type {
     PropertyType::Flat => "flat",
     PropertyType::House => "house",
+    PropertyType::Room => "room",
   };
   let contract_type = match property_data.contract_type {
     ContractType::Buy => "Buying",
     ContractType::Rent => "Renting",
+    ContractType::Temporary => "Subletting",
   };
   let mut msg = String::from(format!(
     "Hey guys, found <b>a new {} on {}</b>!<br /><br />",
     property_type, property.source,
   ));
   msg.push_str(&format!("{}<br />", property_data.address));
-  msg.push_str(&format!(
-    "{} the {} costs <b>{} €</b>.<br />",
-    contract_type,
-    property_type,
-    (property_data.price as i32).to_formatted_string(&Locale::en)
-  ));
-  msg.push_str(&format!(
-    "It has <b>{} rooms</b> and <b>{} sqm</b>.<br />",
-    property_data.rooms,
-    (property_data.squaremeters as i32).to_formatted_string(&Locale::en)
-  ));
+  if let Some(price) = property_data.price() {
+    msg.push_str(&format!(
+      "{} the {} costs <b>{} {}</b>.<br />",
+      contract_type,
+      property_type,
+      (price as i32).to_formatted_string(&Locale::en),
+      property_data.currency.symbol()
+    ));
+  }
+  if let Some(warm_rent) = property_data.total_rent() {
+    msg.push_str(&format!(
+      "Including service charges it costs <b>{} {}</b>.<br />",
+      (warm_rent as i32).to_formatted_string(&Locale::en),
+      property_data.currency.symbol()
+    ));
+  }
+  if let Some(deposit) = property_data.deposit {
+    msg.push_str(&format!(
+      "The deposit is <b>{} {}</b>.<br />",
+      (deposit as i32).to_formatted_string(&Locale::en),
+      property_data.currency.symbol()
+    ));
+  }
+  match property_data.room {
+    Some(ref room) => {
+      msg.push_str(&format!(
+        "It has <b>{} sqm</b>.<br />",
+        (property_data.squaremeters as i32).to_formatted_string(&Locale::en)
+      ));
+      if let Some(flatmates) = room.flatmates {
+        msg.push_str(&format!(
+          "It is shared with <b>{} flatmates</b>.<br />",
+          flatmates
+        ));
+      }
+      if let Some(wanted) = room.wanted() {
+        msg.push_str(&format!(
+          "The flatmates are looking for <b>{}</b>.<br />",
+          wanted
+        ));
+      }
+    }
+    None => msg.push_str(&format!(
+      "It has <b>{} rooms</b> and <b>{} sqm</b>.<br />",
+      property_data.rooms,
+      (property_data.squaremeters as i32).to_formatted_string(&Locale::en)
+    )),
+  }
   if property_data.plot_squaremeters.is_some() {
     msg.push_str(&format!(
       "Plot of land has a size of <b>{} sqm</b>.<br />",
       (property_data.plot_squaremeters.unwrap() as i32).to_formatted_string(&Locale::en),
     ));
   }
+  match (property_data.available_from, property_data.available_until) {
+    (Some(from), Some(until)) => msg.push_str(&format!(
+      "It is available from <b>{}</b> until <b>{}</b>.<br />",
+      from.format("%d.%m.%Y"),
+      until.format("%d.%m.%Y")
+    )),
+    (None, Some(until)) => msg.push_str(&format!(
+      "It is available until <b>{}</b>.<br />",
+      until.format("%d.%m.%Y")
+    )),
+    (Some(from), None) => msg.push_str(&format!(
+      "It is available from <b>{}</b>.<br />",
+      from.format("%d.%m.%Y")
+    )),
+    (None, None) => (),
+  }
   msg.push_str("<br />");
   msg.push_str(&format!(
     "<a href='{}' target='_blank'>Find more information here ...</a>",
@@ -14,8 +14,16 @@ impl From<std::io::Error> for ObserverError {
   }
 }
 
+impl From<csv::Error> for ObserverError {
+  fn from(e: csv::Error) -> ObserverError {
+    ObserverError {
+      message: format!("{}", e),
+    }
+  }
+}
+
 #[async_trait]
-pub trait Observer {
+pub trait Observer: Send + Sync {
   fn name(&self) -> String;
   fn init(&mut self, app_config: &ApplicationConfig) -> Result<(), String>;
   async fn observation(
@@ -23,4 +31,18 @@ pub trait Observer {
     app_config: &ApplicationConfig,
     property: &Property,
   ) -> Result<(), ObserverError>;
+
+  /// Persists whatever the observer did not persist yet, called at the end of each run.
+  async fn flush(&self, _app_config: &ApplicationConfig) -> Result<(), ObserverError> {
+    Ok(())
+  }
+
+  /// Informs about problems of the watcher itself, e.g. a crawler that stopped finding results.
+  async fn alert(
+    &self,
+    _app_config: &ApplicationConfig,
+    _message: &str,
+  ) -> Result<(), ObserverError> {
+    Ok(())
+  }
 }
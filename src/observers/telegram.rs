@@ -29,43 +29,123 @@ impl Observer for Telegram {
         let property_type = match property_data.property_type {
           PropertyType::Flat => "flat",
           PropertyType::House => "house",
+          PropertyType::Room => "room",
         };
         let contract_type = match property_data.contract_type {
           ContractType::Buy => "Buying",
           ContractType::Rent => "Renting",
+          ContractType::Temporary => "Subletting",
         };
         let mut msg = String::from(format!(
           "Hey guys, found *a new {} on {}*!\n",
           property_type, property.source,
         ));
-        msg.push_str(&format!("{}\n", property_data.address));
-        msg.push_str(&format!("[{}]({})\n", property_data.title, url));
+        msg.push_str(&format!("{}\n", escape_markdown(&property_data.address)));
         msg.push_str(&format!(
-          "{} the {} costs *{} €*.\n",
-          contract_type,
-          property_type,
-          (property_data.price as i32).to_formatted_string(&Locale::en)
-        ));
-        msg.push_str(&format!(
-          "It has *{} rooms* and *{} sqm*.\n",
-          property_data.rooms,
-          (property_data.squaremeters as i32).to_formatted_string(&Locale::en)
+          "[{}]({})\n",
+          escape_markdown(&property_data.title),
+          url
         ));
+        if let Some(price) = property_data.price() {
+          msg.push_str(&format!(
+            "{} the {} costs *{} {}*.\n",
+            contract_type,
+            property_type,
+            (price as i32).to_formatted_string(&Locale::en),
+            property_data.currency.symbol()
+          ));
+        }
+        if let Some(warm_rent) = property_data.total_rent() {
+          msg.push_str(&format!(
+            "Including service charges it costs *{} {}*.\n",
+            (warm_rent as i32).to_formatted_string(&Locale::en),
+            property_data.currency.symbol()
+          ));
+        }
+        if let Some(deposit) = property_data.deposit {
+          msg.push_str(&format!(
+            "The deposit is *{} {}*.\n",
+            (deposit as i32).to_formatted_string(&Locale::en),
+            property_data.currency.symbol()
+          ));
+        }
+        match property_data.room {
+          Some(ref room) => {
+            msg.push_str(&format!(
+              "It has *{} sqm*.\n",
+              (property_data.squaremeters as i32).to_formatted_string(&Locale::en)
+            ));
+            if let Some(flatmates) = room.flatmates {
+              msg.push_str(&format!("It is shared with *{} flatmates*.\n", flatmates));
+            }
+            if let Some(wanted) = room.wanted() {
+              msg.push_str(&format!("The flatmates are looking for *{}*.\n", wanted));
+            }
+          }
+          None => msg.push_str(&format!(
+            "It has *{} rooms* and *{} sqm*.\n",
+            property_data.rooms,
+            (property_data.squaremeters as i32).to_formatted_string(&Locale::en)
+          )),
+        }
         if property_data.plot_squaremeters.is_some() {
           msg.push_str(&format!(
-            "Plot of land has a size of *{} sqm*.",
+            "Plot of land has a size of *{} sqm*.\n",
             (property_data.plot_squaremeters.unwrap() as i32).to_formatted_string(&Locale::en),
           ));
         }
-        send_telegram_message(app_config, msg).await;
+        match (property_data.available_from, property_data.available_until) {
+          (Some(from), Some(until)) => msg.push_str(&format!(
+            "It is available from *{}* until *{}*.\n",
+            from.format("%d.%m.%Y"),
+            until.format("%d.%m.%Y")
+          )),
+          (None, Some(until)) => msg.push_str(&format!(
+            "It is available until *{}*.\n",
+            until.format("%d.%m.%Y")
+          )),
+          (Some(from), None) => msg.push_str(&format!(
+            "It is available from *{}*.\n",
+            from.format("%d.%m.%Y")
+          )),
+          (None, None) => (),
+        }
+        send_telegram_message(app_config, msg).await
       }
-      None => (),
+      None => Ok(()),
     }
-    Ok(())
   }
+
+  async fn alert(
+    &self,
+    app_config: &ApplicationConfig,
+    message: &str,
+  ) -> Result<(), ObserverError> {
+    send_telegram_message(
+      app_config,
+      format!("*Attention:* {}", escape_markdown(message)),
+    )
+    .await
+  }
+}
+
+/// Escapes the characters that would otherwise start a formatting, eg. the `_` of a css class
+/// in an alert, which Telegram rejects as an unterminated entity.
+fn escape_markdown(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for character in text.chars() {
+    if let '_' | '*' | '`' | '[' = character {
+      escaped.push('\\');
+    }
+    escaped.push(character);
+  }
+  escaped
 }
 
-async fn send_telegram_message(app_config: &ApplicationConfig, msg: String) -> () {
+async fn send_telegram_message(
+  app_config: &ApplicationConfig,
+  msg: String,
+) -> Result<(), ObserverError> {
   let chat_id = &app_config.telegram.chat_id;
   let api_key = &app_config.telegram.api_key;
 
@@ -85,14 +165,30 @@ async fn send_telegram_message(app_config: &ApplicationConfig, msg: String) -> (
     .await;
 
   match result {
-    Ok(response) => {
-      if response.status() != 200 {
-        println!(
-          "Error while sending message: {:?}",
-          response.text().await.unwrap()
-        )
-      }
-    }
-    Err(e) => println!("{}", e),
+    Ok(response) if response.status() == 200 => Ok(()),
+    Ok(response) => Err(ObserverError {
+      message: format!(
+        "Telegram rejected the message with status {}: {}",
+        response.status(),
+        response.text().await.unwrap_or_default()
+      ),
+    }),
+    Err(e) => Err(ObserverError {
+      message: e.to_string(),
+    }),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::escape_markdown;
+
+  #[test]
+  fn escape_css_selectors() {
+    assert_eq!(
+      escape_markdown("No element found for selector '.hardfacts_3 strong'!"),
+      "No element found for selector '.hardfacts\\_3 strong'!"
+    );
+    assert_eq!(escape_markdown("[*`"), "\\[\\*\\`");
   }
 }
@@ -0,0 +1,304 @@
+use crate::configuration::ApplicationConfig;
+use crate::crawlers::{self, Config, Crawler, HttpClient};
+use crate::enrichers::Enricher;
+use crate::filters::Filter;
+use crate::models::Property;
+use crate::observers::Observer;
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+/// Stages that found properties pass through: filters, enrichers (including the expose) and observers.
+pub struct Pipeline<'a> {
+  pub app_config: &'a ApplicationConfig,
+  pub crawlers: &'a Vec<Box<dyn Crawler>>,
+  pub http: &'a HttpClient,
+  pub filters: &'a Mutex<Vec<Box<dyn Filter>>>,
+  pub enrichers: &'a Vec<Box<dyn Enricher>>,
+  pub observers: &'a Vec<Box<dyn Observer>>,
+}
+
+impl<'a> Pipeline<'a> {
+  /// Processes the properties of each watcher as soon as they arrive and returns those,
+  /// which passed all filters. Each stage works on a limited number of items at the same time
+  /// (see `PipelineConfig`), a slow observer therefore does not hold up filtering further properties.
+  pub async fn process(
+    &self,
+    batches: impl Stream<Item = (Config, Vec<Property>)>,
+  ) -> Vec<Property> {
+    let config = &self.app_config.pipeline;
+    batches
+      .map(|(watcher, batch)| self.filter(watcher, batch))
+      .buffer_unordered(config.filter_concurrency.max(1))
+      .flat_map(stream::iter)
+      .map(|(watcher, property)| self.enrich(watcher, property))
+      .buffer_unordered(config.enricher_concurrency.max(1))
+      .filter_map(future::ready)
+      .map(|property| self.observe(property))
+      .buffer_unordered(config.observer_concurrency.max(1))
+      .collect()
+      .await
+  }
+
+  /// Returns the properties of a watcher, which passed all filters, along with the watcher.
+  /// The filters get to see all properties of the watcher, eg. to look them up at once.
+  async fn filter(&self, watcher: Config, batch: Vec<Property>) -> Vec<(Config, Property)> {
+    let mut passed = vec![];
+    // the filters are shared by all watchers, so they are locked once for the whole batch
+    let mut filters = self.filters.lock().await;
+    for property in &batch {
+      // all filters see each property, so they can remember it even if another filter removes it
+      let mut results = vec![];
+      for filter in filters.iter_mut() {
+        match filter.filter(self.app_config, property, &batch).await {
+          Ok(result) => results.push((filter.name(), result)),
+          Err(err) => eprintln!("Error during filter {}: {}", &filter.name(), err.message),
+        }
+      }
+      match results.iter().find(|(_, result)| !result) {
+        Some((name, _)) => println!("> {} removed by filter {}.", property.id(), name),
+        None => passed.push((watcher.clone(), property.clone())),
+      }
+    }
+    passed
+  }
+
+  /// Returns the enriched property, unless the filters removed it after its expose has been fetched.
+  async fn enrich(&self, watcher: Config, mut property: Property) -> Option<Property> {
+    if let Ok(crawler) = crawlers::get_crawler(&property.source, self.crawlers) {
+      if crawler.has_expose() {
+        println!("> fetching expose of {}.", property.id());
+        let expose = crawlers::fetch_expose(
+          self.app_config,
+          self.http,
+          &watcher,
+          crawler.as_ref(),
+          &property,
+        );
+        match expose.await {
+          Ok(completed_property) => property = completed_property,
+          Err(err) => eprintln!("Error while fetching expose: {}", err),
+        }
+        if !self.filter_expose(&property).await {
+          return None;
+        }
+      }
+    }
+
+    let property_ref = &property;
+    let app_config = self.app_config;
+    futures::future::join_all(self.enrichers.iter().map(|enricher| async move {
+      println!(
+        "> running enricher {} on {}.",
+        &enricher.name(),
+        property_ref.id()
+      );
+      match enricher.enrich(app_config, property_ref).await {
+        Ok(enrichments) => enrichments,
+        Err(err) => {
+          eprintln!(
+            "Error while running enricher {}: {}",
+            &enricher.name(),
+            err.message
+          );
+          HashMap::new()
+        }
+      }
+    }))
+    .await
+    .into_iter()
+    .flatten()
+    .for_each(|(k, v)| {
+      property.enrichments.insert(k, v);
+    });
+    Some(property)
+  }
+
+  /// Returns whether the property passed all filters once more, now that its expose is known.
+  async fn filter_expose(&self, property: &Property) -> bool {
+    let mut filters = self.filters.lock().await;
+    for filter in filters.iter_mut() {
+      match filter.filter_expose(self.app_config, property).await {
+        Ok(true) => (),
+        Ok(false) => {
+          println!(
+            "> {} removed by filter {} after fetching its expose.",
+            property.id(),
+            filter.name()
+          );
+          return false;
+        }
+        Err(err) => eprintln!("Error during filter {}: {}", &filter.name(), err.message),
+      }
+    }
+    true
+  }
+
+  async fn observe(&self, property: Property) -> Property {
+    let property_ref = &property;
+    let app_config = self.app_config;
+    futures::future::join_all(self.observers.iter().map(|observer| async move {
+      println!(
+        "> running observer {} on {}.",
+        &observer.name(),
+        property_ref.id()
+      );
+      match observer.observation(app_config, property_ref).await {
+        Ok(_) => (),
+        Err(err) => eprintln!(
+          "Error while running observer {}: {}",
+          &observer.name(),
+          err.message
+        ),
+      }
+    }))
+    .await;
+    property
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Pipeline;
+  use crate::configuration::ApplicationConfig;
+  use crate::crawlers::fixtures::{self, FixtureMode};
+  use crate::crawlers::{self, Config, HttpClient};
+  use crate::filters::{CriteriaFilter, Filter, FilterError};
+  use crate::models::Property;
+  use async_trait::async_trait;
+  use chrono::NaiveDate;
+  use futures::stream;
+  use tokio::sync::Mutex;
+
+  fn watcher() -> Config {
+    serde_json::from_str(
+      r#"{
+        "address": "https://www.immobilienscout24.de/Suche/de/bayern/muenchen/wohnung-mieten",
+        "city": "München",
+        "crawler": "immoscout",
+        "property_type": "Flat",
+        "contract_type": "Rent"
+      }"#,
+    )
+    .unwrap()
+  }
+
+  /// Removes properties that are more expensive than the average of their watcher.
+  struct BelowAverage {}
+
+  #[async_trait]
+  impl Filter for BelowAverage {
+    fn name(&self) -> String {
+      String::from("below_average")
+    }
+
+    fn init(&mut self, _: &ApplicationConfig) -> Result<(), String> {
+      Ok(())
+    }
+
+    async fn filter(
+      &mut self,
+      _: &ApplicationConfig,
+      property: &Property,
+      properties: &Vec<Property>,
+    ) -> Result<bool, FilterError> {
+      let price = |property: &Property| property.data.as_ref().and_then(|data| data.price());
+      let average = properties.iter().filter_map(price).sum::<f32>() / properties.len() as f32;
+      Ok(price(property).unwrap_or_default() <= average)
+    }
+  }
+
+  #[test]
+  fn filters_see_all_properties_of_a_watcher() {
+    // GIVEN
+    let app_config: ApplicationConfig = serde_json::from_str(r#"{ "watchers": [] }"#).unwrap();
+    let crawlers = crawlers::get_crawlers(&app_config);
+    let http = HttpClient::new(&app_config.http).unwrap();
+    let filters: Mutex<Vec<Box<dyn Filter>>> = Mutex::new(vec![Box::new(BelowAverage {})]);
+    let pipeline = Pipeline {
+      app_config: &app_config,
+      crawlers: &crawlers,
+      http: &http,
+      filters: &filters,
+      enrichers: &vec![],
+      observers: &vec![],
+    };
+    let batches = vec![
+      (
+        watcher(),
+        vec![
+          Property::dummy(500.0, 50.0, 2.0),
+          Property::dummy(1500.0, 50.0, 2.0),
+        ],
+      ),
+      (watcher(), vec![Property::dummy(1000.0, 50.0, 2.0)]),
+    ];
+
+    // WHEN
+    let processed = futures::executor::block_on(pipeline.process(stream::iter(batches)));
+
+    // THEN
+    let mut prices: Vec<f32> = processed
+      .iter()
+      .filter_map(|property| property.data.as_ref().and_then(|data| data.price()))
+      .collect();
+    prices.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(prices, vec![500.0, 1000.0]);
+  }
+
+  #[test]
+  fn criteria_apply_to_details_of_the_expose() {
+    // GIVEN
+    let mut app_config: ApplicationConfig = serde_json::from_str(r#"{ "watchers": [] }"#).unwrap();
+    let directory = std::env::temp_dir().join(format!("properwatcher-{}", std::process::id()));
+    app_config.fixtures.mode = FixtureMode::Replay;
+    app_config.fixtures.directory = directory.to_str().unwrap().to_owned();
+    app_config.blocking.min_body_size = 0;
+    app_config.criteria.available_before = Some(NaiveDate::from_ymd(2026, 12, 1));
+    let expose = |id: &str, available_from: &str| {
+      let mut property = Property::dummy(950.0, 60.0, 2.0);
+      property.source = String::from("immoscout");
+      if let Some(ref mut data) = property.data {
+        data.externalid = id.to_owned();
+        data.url = format!("https://www.immobilienscout24.de/expose/{}", id);
+        let body = format!(
+          r#"<html><body><dd class="is24qa-bezugsfrei-ab">{}</dd></body></html>"#,
+          available_from
+        );
+        fixtures::record(
+          &app_config.fixtures.directory,
+          "immoscout",
+          &data.url,
+          body.as_bytes(),
+          Some("text/html; charset=utf-8"),
+        )
+        .unwrap();
+      }
+      property
+    };
+    let batches = vec![(
+      watcher(),
+      vec![expose("1", "01.11.2026"), expose("2", "ab 01.03.2027")],
+    )];
+    let crawlers = crawlers::get_crawlers(&app_config);
+    let http = HttpClient::new(&app_config.http).unwrap();
+    let filters: Mutex<Vec<Box<dyn Filter>>> = Mutex::new(vec![Box::new(CriteriaFilter {})]);
+    let pipeline = Pipeline {
+      app_config: &app_config,
+      crawlers: &crawlers,
+      http: &http,
+      filters: &filters,
+      enrichers: &vec![],
+      observers: &vec![],
+    };
+
+    // WHEN
+    let processed = futures::executor::block_on(pipeline.process(stream::iter(batches)));
+
+    // THEN
+    std::fs::remove_dir_all(&directory).unwrap();
+    let ids: Vec<String> = processed.iter().map(|property| property.id()).collect();
+    assert_eq!(ids, vec![String::from("immoscout-1")]);
+  }
+}
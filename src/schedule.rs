@@ -0,0 +1,416 @@
+use crate::configuration::ApplicationConfig;
+use crate::crawlers::Config;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use rand::Rng;
+use std::str::FromStr;
+
+/// Cron expression with the fields minute, hour, day of month, month and day of week.
+/// Each field is `*`, a value, a range (`1-5`) or a list of them (`0,30`), optionally with a step (`*/15`).
+#[derive(Debug)]
+pub struct Cron {
+  minutes: Vec<bool>,
+  hours: Vec<bool>,
+  days: Vec<bool>,
+  months: Vec<bool>,
+  weekdays: Vec<bool>,
+  any_day: bool,
+  any_weekday: bool,
+}
+
+impl FromStr for Cron {
+  type Err = String;
+
+  fn from_str(expression: &str) -> Result<Self, Self::Err> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    if fields.len() != 5 {
+      return Err(format!(
+        "expected 5 fields (minute hour day month weekday), found {}",
+        fields.len()
+      ));
+    }
+    // sunday can be written as 0 or 7
+    let mut weekdays = parse_field(fields[4], 0, 7)?;
+    weekdays[0] = weekdays[0] || weekdays[7];
+    weekdays.truncate(7);
+    Ok(Cron {
+      minutes: parse_field(fields[0], 0, 59)?,
+      hours: parse_field(fields[1], 0, 23)?,
+      days: parse_field(fields[2], 1, 31)?,
+      months: parse_field(fields[3], 1, 12)?,
+      weekdays,
+      any_day: fields[2].starts_with('*'),
+      any_weekday: fields[4].starts_with('*'),
+    })
+  }
+}
+
+/// Returns for each value up to `max`, whether the field matches it.
+fn parse_field(field: &str, min: usize, max: usize) -> Result<Vec<bool>, String> {
+  let mut values = vec![false; max + 1];
+  for part in field.split(',') {
+    let parse = |value: &str| {
+      value
+        .parse::<usize>()
+        .map_err(|_| format!("'{}' is not a number", value))
+    };
+    let (range, step) = match part.find('/') {
+      Some(index) => (&part[..index], parse(&part[index + 1..])?),
+      None => (part, 1),
+    };
+    let (from, to) = if range == "*" {
+      (min, max)
+    } else if let Some(index) = range.find('-') {
+      (parse(&range[..index])?, parse(&range[index + 1..])?)
+    } else {
+      // a single value with a step, eg. 5/15, repeats up to the maximum
+      let value = parse(range)?;
+      (value, if step > 1 { max } else { value })
+    };
+    if from < min || to > max || from > to || step == 0 {
+      return Err(format!("'{}' is not within {} and {}", part, min, max));
+    }
+    for value in (from..=to).step_by(step) {
+      values[value] = true;
+    }
+  }
+  Ok(values)
+}
+
+impl Cron {
+  fn matches_day(&self, date: NaiveDate) -> bool {
+    let day = self.days[date.day() as usize];
+    let weekday = self.weekdays[date.weekday().num_days_from_sunday() as usize];
+    // as in crontab, a day matches either field, if both are restricted
+    let matches = match (self.any_day, self.any_weekday) {
+      (false, false) => day || weekday,
+      _ => day && weekday,
+    };
+    matches && self.months[date.month() as usize]
+  }
+
+  /// Returns the first minute after the given time, that matches the expression.
+  pub fn next_after(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+    let mut next = time.date().and_hms(time.hour(), time.minute(), 0) + Duration::minutes(1);
+    // expressions like the 31st of february never match
+    let limit = next + Duration::days(4 * 366);
+    while next < limit {
+      if !self.matches_day(next.date()) {
+        next = next.date().succ().and_hms(0, 0, 0);
+      } else if !self.hours[next.hour() as usize] {
+        next = next.date().and_hms(next.hour(), 0, 0) + Duration::hours(1);
+      } else if !self.minutes[next.minute() as usize] {
+        next = next + Duration::minutes(1);
+      } else {
+        return Some(next);
+      }
+    }
+    None
+  }
+}
+
+/// Daily time span in which a watcher is not run, eg. "23:00-07:00".
+#[derive(Debug)]
+pub struct QuietHours {
+  start: NaiveTime,
+  end: NaiveTime,
+}
+
+impl FromStr for QuietHours {
+  type Err = String;
+
+  fn from_str(span: &str) -> Result<Self, Self::Err> {
+    let parse = |time: &str| {
+      NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .map_err(|_| format!("'{}' is not a time like 23:00", time.trim()))
+    };
+    match span.find('-') {
+      Some(index) => Ok(QuietHours {
+        start: parse(&span[..index])?,
+        end: parse(&span[index + 1..])?,
+      }),
+      None => Err(format!("'{}' is not a span like 23:00-07:00", span)),
+    }
+  }
+}
+
+impl QuietHours {
+  /// Returns the end of the quiet hours, if the given time is within them.
+  pub fn end_of(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+    let (start, end, now) = (self.start, self.end, time.time());
+    if start <= end {
+      if now >= start && now < end {
+        Some(time.date().and_time(end))
+      } else {
+        None
+      }
+    } else if now >= start {
+      // quiet hours span midnight
+      Some(time.date().succ().and_time(end))
+    } else if now < end {
+      Some(time.date().and_time(end))
+    } else {
+      None
+    }
+  }
+}
+
+struct Entry {
+  watcher: Config,
+  cron: Option<Cron>,
+  quiet_hours: Option<QuietHours>,
+  next_run: NaiveDateTime,
+}
+
+/// Keeps track of when each watcher is due next.
+/// All watchers are due right away, unless they are within their quiet hours. Later on each watcher
+/// is run according to its cron expression or its interval (the global one if it has none),
+/// delayed by up to `jitter` seconds and postponed to the end of its quiet hours.
+pub struct Timetable {
+  entries: Vec<Entry>,
+  interval: u64,
+}
+
+impl Timetable {
+  /// Schedules are validated when the configuration is read, invalid ones are ignored here.
+  pub fn new(app_config: &ApplicationConfig, now: NaiveDateTime) -> Self {
+    let entries = app_config
+      .watchers
+      .iter()
+      .map(|watcher| {
+        let quiet_hours: Option<QuietHours> = watcher
+          .quiet_hours
+          .as_ref()
+          .and_then(|quiet_hours| quiet_hours.parse().ok());
+        // a watcher that is started within its quiet hours is run once they end
+        let next_run = match quiet_hours
+          .as_ref()
+          .and_then(|quiet_hours| quiet_hours.end_of(now))
+        {
+          Some(end) => end + Duration::seconds(random(watcher.jitter) as i64),
+          None => now,
+        };
+        Entry {
+          watcher: watcher.clone(),
+          cron: watcher.cron.as_ref().and_then(|cron| cron.parse().ok()),
+          quiet_hours,
+          next_run,
+        }
+      })
+      .collect();
+    Timetable {
+      entries,
+      interval: app_config.interval,
+    }
+  }
+
+  /// Returns the watchers that are due and schedules their next run.
+  pub fn take_due(&mut self, now: NaiveDateTime) -> Vec<Config> {
+    let interval = self.interval;
+    self
+      .entries
+      .iter_mut()
+      .filter(|entry| entry.next_run <= now)
+      .map(|entry| {
+        entry.next_run = Self::schedule(entry, interval, now);
+        entry.watcher.clone()
+      })
+      .collect()
+  }
+
+  /// Returns all watchers, eg. for a run that has been triggered manually, and schedules their next run.
+  pub fn take_all(&mut self, now: NaiveDateTime) -> Vec<Config> {
+    let interval = self.interval;
+    self
+      .entries
+      .iter_mut()
+      .map(|entry| {
+        entry.next_run = Self::schedule(entry, interval, now);
+        entry.watcher.clone()
+      })
+      .collect()
+  }
+
+  /// Time at which the next watcher is due.
+  pub fn next_run(&self) -> Option<NaiveDateTime> {
+    self.entries.iter().map(|entry| entry.next_run).min()
+  }
+
+  fn schedule(entry: &Entry, interval: u64, after: NaiveDateTime) -> NaiveDateTime {
+    let interval = Duration::seconds(entry.watcher.interval.unwrap_or(interval) as i64);
+    let planned = match entry.cron {
+      Some(ref cron) => cron.next_after(after).unwrap_or(after + interval),
+      None => after + interval,
+    };
+    // jitter also spreads watchers that leave their quiet hours at the same time
+    let jitter = Duration::seconds(random(entry.watcher.jitter) as i64);
+    match entry.quiet_hours {
+      Some(ref quiet_hours) => quiet_hours.end_of(planned).unwrap_or(planned) + jitter,
+      None => planned + jitter,
+    }
+  }
+}
+
+/// Returns a random number up to `max`, uniformly distributed.
+fn random(max: u64) -> u64 {
+  rand::thread_rng().gen_range(0, max + 1)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{random, Cron, QuietHours, Timetable};
+  use crate::configuration::ApplicationConfig;
+  use chrono::{NaiveDate, NaiveDateTime};
+
+  fn time(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    // the 1st of march 2021 is a monday
+    NaiveDate::from_ymd(2021, 3, day).and_hms(hour, minute, 0)
+  }
+
+  #[test]
+  fn cron_next_run() {
+    // GIVEN
+    let every_two_minutes: Cron = "*/2 * * * *".parse().unwrap();
+    let hourly: Cron = "15 * * * *".parse().unwrap();
+    let weekdays: Cron = "0 8,18 * * 1-5".parse().unwrap();
+
+    // WHEN
+    let after = NaiveDate::from_ymd(2021, 3, 5).and_hms(18, 0, 30);
+
+    // THEN
+    assert_eq!(every_two_minutes.next_after(after), Some(time(5, 18, 2)));
+    assert_eq!(hourly.next_after(after), Some(time(5, 18, 15)));
+    assert_eq!(weekdays.next_after(after), Some(time(8, 8, 0)));
+  }
+
+  #[test]
+  fn cron_day_or_weekday() {
+    // GIVEN
+    let first_or_sunday: Cron = "0 0 1 * 7".parse().unwrap();
+    let impossible: Cron = "0 0 31 2 *".parse().unwrap();
+
+    // WHEN
+    let after = time(1, 12, 0);
+
+    // THEN
+    assert_eq!(first_or_sunday.next_after(after), Some(time(7, 0, 0)));
+    assert_eq!(impossible.next_after(after), None);
+  }
+
+  #[test]
+  fn cron_invalid() {
+    assert!("* * * *".parse::<Cron>().is_err());
+    assert!("60 * * * *".parse::<Cron>().is_err());
+    assert!("*/0 * * * *".parse::<Cron>().is_err());
+    assert!("a * * * *".parse::<Cron>().is_err());
+  }
+
+  #[test]
+  fn quiet_hours_over_midnight() {
+    // GIVEN
+    let quiet_hours: QuietHours = "23:00-07:00".parse().unwrap();
+
+    // WHEN
+    let late = quiet_hours.end_of(time(1, 23, 30));
+    let early = quiet_hours.end_of(time(2, 6, 59));
+    let day = quiet_hours.end_of(time(2, 7, 0));
+
+    // THEN
+    assert_eq!(late, Some(time(2, 7, 0)));
+    assert_eq!(early, Some(time(2, 7, 0)));
+    assert_eq!(day, None);
+  }
+
+  #[test]
+  fn quiet_hours_within_day() {
+    // GIVEN
+    let quiet_hours: QuietHours = "12:00-14:00".parse().unwrap();
+
+    // THEN
+    assert_eq!(quiet_hours.end_of(time(1, 13, 0)), Some(time(1, 14, 0)));
+    assert_eq!(quiet_hours.end_of(time(1, 11, 59)), None);
+    assert!("12:00".parse::<QuietHours>().is_err());
+  }
+
+  #[test]
+  fn jitter_is_bounded() {
+    assert_eq!(random(0), 0);
+    assert!((0..100).all(|_| random(30) <= 30));
+  }
+
+  #[test]
+  fn jitter_is_spread() {
+    // GIVEN
+    let mut counts = [0; 10];
+
+    // WHEN
+    for _ in 0..10_000 {
+      counts[random(9) as usize] += 1;
+    }
+
+    // THEN
+    // each value is expected 1,000 times, the bounds are far beyond any random deviation
+    assert!(counts.iter().all(|count| *count > 800 && *count < 1200));
+  }
+
+  #[test]
+  fn timetable_runs_due_watchers() {
+    // GIVEN
+    let app_config: ApplicationConfig = serde_json::from_str(
+      r#"{
+        "interval": 3600,
+        "watchers": [
+          { "address": "", "city": "Munich", "crawler": "immoscout", "property_type": "Flat",
+            "contract_type": "Rent", "interval": 120 },
+          { "address": "", "city": "Munich", "crawler": "sueddeutsche", "property_type": "Flat",
+            "contract_type": "Rent", "quiet_hours": "23:00-07:00" },
+          { "address": "", "city": "Munich", "crawler": "wggesucht", "property_type": "Flat",
+            "contract_type": "Rent", "cron": "30 * * * *" }
+        ]
+      }"#,
+    )
+    .unwrap();
+    let mut timetable = Timetable::new(&app_config, time(1, 22, 10));
+
+    // WHEN
+    let first = timetable.take_due(time(1, 22, 10));
+    let none = timetable.take_due(time(1, 22, 11));
+    let second = timetable.take_due(time(1, 22, 12));
+    let third = timetable.take_due(time(1, 22, 30));
+
+    // THEN
+    assert_eq!(first.len(), 3);
+    assert!(none.is_empty());
+    assert_eq!(second[0].crawler, "immoscout");
+    assert_eq!(third.len(), 2);
+    assert_eq!(third[1].crawler, "wggesucht");
+    // the hourly run of sueddeutsche would be at 23:10 and is postponed to the end of the quiet hours
+    timetable.take_due(time(1, 23, 30));
+    assert_eq!(timetable.next_run(), Some(time(1, 23, 32)));
+    assert_eq!(timetable.entries[1].next_run, time(2, 7, 0));
+  }
+
+  #[test]
+  fn timetable_starts_after_quiet_hours() {
+    // GIVEN
+    let app_config: ApplicationConfig = serde_json::from_str(
+      r#"{
+        "watchers": [
+          { "address": "", "city": "Munich", "crawler": "immoscout", "property_type": "Flat",
+            "contract_type": "Rent", "quiet_hours": "23:00-07:00" },
+          { "address": "", "city": "Munich", "crawler": "wggesucht", "property_type": "Flat",
+            "contract_type": "Rent" }
+        ]
+      }"#,
+    )
+    .unwrap();
+
+    // WHEN
+    let mut timetable = Timetable::new(&app_config, time(2, 3, 0));
+    let due = timetable.take_due(time(2, 3, 0));
+
+    // THEN
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].crawler, "wggesucht");
+    assert_eq!(timetable.entries[0].next_run, time(2, 7, 0));
+  }
+}
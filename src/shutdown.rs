@@ -0,0 +1,68 @@
+use futures::future;
+use tokio::sync::watch;
+
+/// Tells the parts of a run, whether the process has been asked to stop (SIGTERM or SIGINT).
+/// Once requested, no further watchers are started, while those already crawling
+/// and the properties already found are still processed.
+#[derive(Clone)]
+pub struct Shutdown {
+  receiver: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+  /// Listens for SIGTERM and SIGINT, a second signal stops the process right away.
+  pub fn listen() -> Self {
+    let (sender, receiver) = watch::channel(false);
+    tokio::spawn(async move {
+      signal().await;
+      println!("shutting down, send the signal again to stop immediately ...");
+      let _ = sender.broadcast(true);
+      signal().await;
+      eprintln!("stopped immediately.");
+      std::process::exit(130);
+    });
+    Shutdown { receiver }
+  }
+
+  /// A shutdown that is never requested, eg. within a lambda function.
+  pub fn never() -> Self {
+    let (_, receiver) = watch::channel(false);
+    Shutdown { receiver }
+  }
+
+  pub fn is_requested(&self) -> bool {
+    *self.receiver.borrow()
+  }
+
+  /// Completes as soon as the shutdown has been requested.
+  pub async fn requested(&self) {
+    let mut receiver = self.receiver.clone();
+    while !*receiver.borrow() {
+      if receiver.recv().await.is_none() {
+        // nobody is listening for signals, so the shutdown will never be requested
+        future::pending::<()>().await;
+      }
+    }
+  }
+}
+
+#[cfg(unix)]
+async fn signal() {
+  use tokio::signal::unix::{signal, SignalKind};
+  match signal(SignalKind::terminate()) {
+    Ok(mut terminate) => {
+      tokio::select! {
+        _ = terminate.recv() => (),
+        _ = tokio::signal::ctrl_c() => (),
+      }
+    }
+    Err(_) => {
+      let _ = tokio::signal::ctrl_c().await;
+    }
+  }
+}
+
+#[cfg(not(unix))]
+async fn signal() {
+  let _ = tokio::signal::ctrl_c().await;
+}